@@ -0,0 +1,76 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, TxBuilder, TxExpiration},
+    key::common,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    token,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{pipeline, sdk::Sdk};
+
+pub async fn build_bond_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut bond_tx_builder = sdk
+        .namada
+        .new_bond(validator, amount)
+        .source(source_address);
+    bond_tx_builder = bond_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    bond_tx_builder = bond_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        bond_tx_builder = bond_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        bond_tx_builder = bond_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    bond_tx_builder = bond_tx_builder.signing_keys(signers);
+
+    let (bond_tx, signing_data) = bond_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((bond_tx, signing_data, bond_tx_builder.tx))
+}
+
+pub async fn execute_bond_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut bond_tx, signing_data, tx_args) = build_bond_tx(
+        sdk,
+        source_address,
+        validator,
+        gas_payer,
+        signers,
+        amount,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(sdk, &mut bond_tx, &tx_args, signing_data, "Bond").await?;
+
+    pipeline::submit_tx(sdk, bond_tx, &tx_args, "Bond", request_id)
+        .await
+        .map(|_| true)
+}
@@ -0,0 +1,80 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, TxBuilder, TxExpiration},
+    key::common,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{pipeline, sdk::Sdk};
+
+pub async fn build_claim_rewards_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut claim_rewards_tx_builder = sdk
+        .namada
+        .new_claim_rewards(validator)
+        .source(source_address);
+    claim_rewards_tx_builder =
+        claim_rewards_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    claim_rewards_tx_builder = claim_rewards_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        claim_rewards_tx_builder = claim_rewards_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        claim_rewards_tx_builder = claim_rewards_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    claim_rewards_tx_builder = claim_rewards_tx_builder.signing_keys(signers);
+
+    let (claim_rewards_tx, signing_data) = claim_rewards_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((claim_rewards_tx, signing_data, claim_rewards_tx_builder.tx))
+}
+
+pub async fn execute_claim_rewards_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut claim_rewards_tx, signing_data, tx_args) = build_claim_rewards_tx(
+        sdk,
+        source_address,
+        validator,
+        gas_payer,
+        signers,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(
+        sdk,
+        &mut claim_rewards_tx,
+        &tx_args,
+        signing_data,
+        "Claim rewards",
+    )
+    .await?;
+
+    pipeline::submit_tx(sdk, claim_rewards_tx, &tx_args, "Claim rewards", request_id)
+        .await
+        .map(|_| true)
+}
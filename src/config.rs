@@ -1,30 +1,501 @@
-use std::path::PathBuf;
+use std::{env, net::SocketAddr, path::PathBuf};
+
+use crate::shielded::DEFAULT_MASP_INDEXER_URL;
 
 #[derive(clap::Parser, Clone, Debug)]
 pub struct AppConfig {
+    #[clap(long, env, global = true)]
+    pub base_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub password: PasswordArgs,
+
+    #[clap(flatten)]
+    pub signer: SignerArgs,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+impl AppConfig {
+    // the directory holding `wallet.toml` and `shielded.dat`, defaults to the current one
+    pub fn base_dir(&self) -> PathBuf {
+        self.base_dir.clone().unwrap_or(env::current_dir().unwrap())
+    }
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Run the transparent, shielding and unshielding transfer example
+    Run(RunArgs),
+    /// Store a secret key or a spending key encrypted in the wallet
+    ImportKey(ImportKeyArgs),
+    /// Generate a new BIP39 mnemonic
+    GenMnemonic(GenMnemonicArgs),
+    /// Derive a transparent or a shielded key from a BIP39 mnemonic into the wallet
+    DeriveKey(DeriveKeyArgs),
+    /// Generate a transparent keypair and/or a MASP spending key
+    Keygen(KeygenArgs),
+    /// Create an established (multisig) account
+    InitAccount(InitAccountArgs),
+    /// Change the public keys and/or the threshold of an established account
+    UpdateAccount(UpdateAccountArgs),
+    /// Transparent transfer from an established account signed by several keys
+    MultisigTransfer(MultisigTransferArgs),
+    /// Build an unsigned tx and dump it together with its signing data to a file
+    Build(BuildArgs),
+    /// Add signatures from wallet keys to a dumped tx, doesn't need a node
+    Sign(SignArgs),
+    /// Broadcast a fully signed tx file
+    Submit(SubmitArgs),
+    /// Bond tokens to a validator
+    Bond(BondArgs),
+    /// Unbond tokens from a validator
+    Unbond(UnbondArgs),
+    /// Withdraw the unbonded tokens whose withdrawal epoch has been reached
+    Withdraw(WithdrawArgs),
+    /// Move bonded tokens from one validator to another
+    Redelegate(RedelegateArgs),
+    /// Claim the PoS rewards of one or all the validators bonded to
+    ClaimRewards(ClaimRewardsArgs),
+    /// Show the bonds, unbonds and pending rewards of an address
+    StakingReport(StakingReportArgs),
+    /// Vote on a governance proposal
+    Vote(VoteArgs),
+    /// List the governance proposals
+    Proposals(ProposalsArgs),
+    /// Show the details of a governance proposal
+    Proposal(ProposalArgs),
+    /// Send tokens to another chain over IBC
+    IbcTransfer(IbcTransferArgs),
+    /// Generate the memo an IBC sender needs to shield tokens to one of our payment addresses
+    IbcShieldingMemo(IbcShieldingMemoArgs),
+    /// List the txs recorded in the journal, or the status history of one of them
+    History(HistoryArgs),
+    /// Keep a node connection and the shielded context loaded and serve them over a local HTTP API
+    Serve(ServeArgs),
+    /// List the transfers parked by the policy until approved
+    Approvals(ApprovalsArgs),
+    /// Approve or reject a parked transfer, it goes through when run again
+    Approve(ApproveArgs),
+    /// Move the balance of the hot account above its band to a cold address
+    Sweep(SweepArgs),
+    /// Compare the transparent and shielded holdings with the expected ledger
+    Reconcile(ReconcileArgs),
+}
+
+// where to read the wallet password from, the tty is prompted when neither is set
+#[derive(clap::Args, Clone, Debug)]
+pub struct PasswordArgs {
+    #[clap(long, env, global = true, conflicts_with = "password_env")]
+    pub password_file: Option<PathBuf>,
+
+    #[clap(long, env, global = true)]
+    pub password_env: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum SigningBackend {
+    Wallet,
+    Remote,
+    File,
+}
+
+// who signs for the keys that are not in the process wallet
+#[derive(clap::Args, Clone, Debug)]
+pub struct SignerArgs {
+    #[clap(long, env, global = true, value_enum, default_value = "wallet")]
+    pub signing_backend: SigningBackend,
+
+    // `http://127.0.0.1:<port>` or `unix:///path/to/socket`
+    #[clap(long, env, global = true)]
+    pub remote_signer_url: Option<String>,
+
+    // plaintext secret keys, one per line, for tests only
+    #[clap(long, env, global = true)]
+    pub signing_keys_file: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct NodeArgs {
+    // tried in order of their latest height, unhealthy endpoints are skipped
+    #[clap(long, env, value_delimiter = ',')]
+    #[arg(required = true)]
+    pub rpc: Vec<String>,
+
+    // attempts of a single request over all endpoints before giving up
+    #[clap(long, env, default_value_t = 5)]
+    pub rpc_max_attempts: u32,
+
+    // detected from the nodes when omitted, they must all agree then
+    #[clap(long, env)]
+    pub chain_id: Option<String>,
+
+    // address of the expected native token, checked against the node at startup
+    #[clap(long, env)]
+    pub native_token: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct RunArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // wallet alias of the source key, ignored when `--source-private-key` is set
+    #[clap(long, env, default_value = "source")]
+    pub source_alias: String,
+
+    #[clap(long, env)]
+    pub source_private_key: Option<String>,
+
+    // wallet alias of the spending key, ignored when `--spending-key` is set
+    #[clap(long, env, default_value = "spending")]
+    pub spending_key_alias: String,
+
+    #[clap(long, env)]
+    pub spending_key: Option<String>,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub target_address: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    #[clap(long, env)]
+    pub expiration_timestamp_utc: Option<i64>,
+
+    #[clap(long, env)]
+    pub memo: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ImportKeyArgs {
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub alias: String,
+
+    #[clap(flatten)]
+    pub key: ImportedKey,
+
+    // block height from which the shielded sync starts scanning for the spending key
+    #[clap(long, env)]
+    pub birthday: Option<u64>,
+
+    // overwrite an existing wallet entry with the same alias
+    #[clap(long, env)]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+#[group(required = true, multiple = false)]
+pub struct ImportedKey {
+    #[clap(long, env)]
+    pub secret_key: Option<String>,
+
+    #[clap(long, env)]
+    pub spending_key: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum KeyScheme {
+    Ed25519,
+    Secp256k1,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct GenMnemonicArgs {
+    // one of 12, 15, 18, 21 or 24
+    #[clap(long, env, default_value_t = 24)]
+    pub words: usize,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct DeriveKeyArgs {
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub alias: String,
+
+    // derive a MASP spending key (ZIP32) instead of a transparent one (BIP44)
+    #[clap(long, env)]
+    pub shielded: bool,
+
+    #[clap(long, env, value_enum, default_value = "ed25519")]
+    pub scheme: KeyScheme,
+
+    // e.g. `m/44'/877'/0'/0'/0'` or `m/32'/877'/0'`, `default` picks the first account
+    #[clap(long, env, default_value = "default")]
+    pub derivation_path: String,
+
+    // read the mnemonic from a file instead of prompting for it
+    #[clap(long, env)]
+    pub mnemonic_file: Option<PathBuf>,
+
+    // prompt for the optional BIP39 passphrase (the "25th word")
+    #[clap(long, env)]
+    pub bip39_passphrase: bool,
+
+    #[clap(long, env)]
+    pub birthday: Option<u64>,
+
+    #[clap(long, env)]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+#[group(id = "kind", required = true, multiple = true)]
+pub struct KeygenKind {
+    #[clap(long, env)]
+    pub transparent: bool,
+
+    #[clap(long, env)]
+    pub shielded: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct KeygenArgs {
+    #[clap(flatten)]
+    pub kind: KeygenKind,
+
+    #[clap(long, env, value_enum, default_value = "ed25519")]
+    pub scheme: KeyScheme,
+
+    // store the transparent key in the wallet under this alias instead of printing it
+    #[clap(long, env)]
+    pub alias: Option<String>,
+
+    // store the spending key (and its default payment address as `<alias>-addr`) in the wallet
+    #[clap(long, env)]
+    pub spending_key_alias: Option<String>,
+
+    #[clap(long, env)]
+    pub birthday: Option<u64>,
+
+    #[clap(long, env)]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct InitAccountArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // wallet alias under which the new account address is stored
     #[clap(long, env)]
     #[arg(required = true)]
-    pub rpc: String,
+    pub alias: String,
+
+    // public key or wallet alias, repeat for every key of the account
+    #[clap(long = "public-key", env, value_delimiter = ',')]
+    #[arg(required = true)]
+    pub public_keys: Vec<String>,
+
+    #[clap(long, env, default_value_t = 1)]
+    pub threshold: u8,
+
+    // wallet alias of the implicit account paying the fees
+    #[clap(long, env, default_value = "source")]
+    pub gas_payer: String,
 
+    #[clap(long, env)]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct UpdateAccountArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // address or wallet alias of the established account
     #[clap(long, env)]
     #[arg(required = true)]
-    pub source_private_key: String,
+    pub account: String,
+
+    // the new set of public keys, the current one is kept when empty
+    #[clap(long = "public-key", env, value_delimiter = ',')]
+    pub public_keys: Vec<String>,
 
     #[clap(long, env)]
+    pub threshold: Option<u8>,
+
+    // wallet aliases of the current account keys signing the update
+    #[clap(long = "signer", env, value_delimiter = ',')]
     #[arg(required = true)]
-    pub spending_key: String,
+    pub signers: Vec<String>,
+
+    #[clap(long, env, default_value = "source")]
+    pub gas_payer: String,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct MultisigTransferArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // address or wallet alias of the established account
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub account: String,
+
+    // wallet aliases of the account keys signing the transfer
+    #[clap(long = "signer", env, value_delimiter = ',')]
+    #[arg(required = true)]
+    pub signers: Vec<String>,
+
+    #[clap(long, env, default_value = "source")]
+    pub gas_payer: String,
 
     #[clap(long, env)]
     #[arg(required = true)]
     pub target_address: String,
 
+    // token address, wallet alias or IBC denom trace
+    #[clap(long, env, default_value = "nam")]
+    pub token: String,
+
     #[clap(long, env)]
     #[arg(required = true)]
     pub amount: u64,
 
     #[clap(long, env)]
+    pub expiration_timestamp_utc: Option<i64>,
+
+    #[clap(long, env)]
+    pub memo: Option<String>,
+
+    // a re-run with the same id returns the outcome of the first run instead of sending a new tx
+    #[clap(long, env)]
+    pub request_id: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct BuildArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // file the unsigned tx is written to
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub dump: PathBuf,
+
+    // public key or wallet alias of the implicit account paying the fees
+    #[clap(long, env, default_value = "source")]
+    pub gas_payer: String,
+
+    // public keys or wallet aliases expected to sign, defaults to the gas payer
+    #[clap(long = "signer", env, value_delimiter = ',')]
+    pub signers: Vec<String>,
+
+    #[clap(long, env)]
+    pub expiration_timestamp_utc: Option<i64>,
+
+    #[clap(long, env)]
+    pub memo: Option<String>,
+
+    #[clap(subcommand)]
+    pub kind: BuildKind,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum BuildKind {
+    /// Transparent transfer
+    Transparent {
+        // address or wallet alias
+        #[clap(long, env)]
+        source: String,
+
+        #[clap(long, env)]
+        target_address: String,
+
+        // token address, wallet alias or IBC denom trace
+        #[clap(long, env, default_value = "nam")]
+        token: String,
+
+        #[clap(long, env)]
+        amount: u64,
+    },
+    /// Shielding transfer to a payment address
+    Shielding {
+        #[clap(long, env)]
+        source: String,
+
+        #[clap(long, env)]
+        target_address: String,
+
+        // token address, wallet alias or IBC denom trace
+        #[clap(long, env, default_value = "nam")]
+        token: String,
+
+        #[clap(long, env)]
+        amount: u64,
+    },
+    /// Unshielding transfer, the spending key is needed to build the MASP proofs
+    Unshielding {
+        #[clap(long, env, default_value = "spending")]
+        spending_key_alias: String,
+
+        #[clap(long, env)]
+        spending_key: Option<String>,
+
+        #[clap(long, env)]
+        target_address: String,
+
+        // token address, wallet alias or IBC denom trace
+        #[clap(long, env, default_value = "nam")]
+        token: String,
+
+        #[clap(long, env)]
+        amount: u64,
+    },
+    /// Reveal the public key of an implicit account
+    RevealPk {
+        // public key or wallet alias
+        #[clap(long, env)]
+        public_key: String,
+    },
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct SignArgs {
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub tx_file: PathBuf,
+
+    // wallet aliases of the keys to sign with
+    #[clap(long = "signer", env, value_delimiter = ',')]
     #[arg(required = true)]
-    pub chain_id: String,
+    pub signers: Vec<String>,
+
+    // where to write the signed tx, defaults to overwriting `--tx-file`
+    #[clap(long, env)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct SubmitArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub tx_file: PathBuf,
+
+    // a re-run with the same id returns the outcome of the first run instead of sending a new tx
+    #[clap(long, env)]
+    pub request_id: Option<String>,
+}
+
+// fee payer, signers and wrapper options shared by the commands submitting a tx
+#[derive(clap::Args, Clone, Debug)]
+pub struct TxSigningArgs {
+    // wallet alias or public key of the implicit account paying the fees
+    #[clap(long, env, default_value = "source")]
+    pub gas_payer: String,
+
+    // wallet aliases or public keys signing the tx, defaults to the gas payer
+    #[clap(long = "signer", env, value_delimiter = ',')]
+    pub signers: Vec<String>,
 
     #[clap(long, env)]
     pub expiration_timestamp_utc: Option<i64>,
@@ -32,6 +503,460 @@ pub struct AppConfig {
     #[clap(long, env)]
     pub memo: Option<String>,
 
+    // a re-run with the same id returns the outcome of the first run instead of sending a new tx
     #[clap(long, env)]
-    pub base_dir: Option<PathBuf>,
+    pub request_id: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct BondArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // address or wallet alias of the delegator
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub validator: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct UnbondArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub validator: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct WithdrawArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub validator: String,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct RedelegateArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub src_validator: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub dest_validator: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ClaimRewardsArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    // claim from every validator the source is bonded to when omitted
+    #[clap(long, env)]
+    pub validator: Option<String>,
+
+    // bond the claimed rewards back to the same validator
+    #[clap(long, env)]
+    pub restake: bool,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct StakingReportArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum VoteOption {
+    Yay,
+    Nay,
+    Abstain,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct VoteArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // address or wallet alias of the validator or delegator voting
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub proposal_id: u64,
+
+    #[clap(long, env, value_enum)]
+    #[arg(required = true)]
+    pub vote: VoteOption,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ProposalStatusFilter {
+    Pending,
+    Active,
+    Ended,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ProposalsArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // only list the proposals in this state, all of them otherwise
+    #[clap(long, env, value_enum)]
+    pub status: Option<ProposalStatusFilter>,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ProposalArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[arg(required = true)]
+    pub proposal_id: u64,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct IbcTransferArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // address or wallet alias of the transparent source, ignored with --shielded
+    #[clap(long, env, default_value = "source")]
+    pub source: String,
+
+    // spend from the shielded pool with the spending key instead of the transparent source
+    #[clap(long, env)]
+    pub shielded: bool,
+
+    #[clap(long, env, default_value = "spending")]
+    pub spending_key_alias: String,
+
+    #[clap(long, env)]
+    pub spending_key: Option<String>,
+
+    // bech32 address on the counterparty chain
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub receiver: String,
+
+    // token address, wallet alias or IBC denom trace
+    #[clap(long, env, default_value = "nam")]
+    pub token: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub channel_id: String,
+
+    #[clap(long, env, default_value = "transfer")]
+    pub port_id: String,
+
+    // height of the counterparty chain after which the packet times out
+    #[clap(long, env)]
+    pub timeout_height: Option<u64>,
+
+    // seconds after which the packet times out, the sdk default is used when neither timeout is set
+    #[clap(long, env)]
+    pub timeout_sec_offset: Option<u64>,
+
+    // address or payment address the tokens go back to if the packet fails or times out
+    #[clap(long, env)]
+    pub refund_target: Option<String>,
+
+    // memo of the ICS-20 packet, `--memo` is the memo of the Namada tx
+    #[clap(long, env)]
+    pub ibc_memo: Option<String>,
+
+    #[clap(flatten)]
+    pub tx: TxSigningArgs,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct IbcShieldingMemoArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // payment address or wallet alias, a fresh one is derived from the spending key otherwise
+    #[clap(long, env)]
+    pub payment_address: Option<String>,
+
+    #[clap(long, env, default_value = "spending")]
+    pub spending_key_alias: String,
+
+    #[clap(long, env)]
+    pub spending_key: Option<String>,
+
+    // Namada token address, alias or IBC denom trace, or the denom of the token on the sending chain
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub token: String,
+
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub amount: u64,
+
+    // channel on the Namada side the transfer is received on
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub channel_id: String,
+
+    #[clap(long, env, default_value = "transfer")]
+    pub port_id: String,
+
+    #[clap(long, env)]
+    pub expiration_timestamp_utc: Option<i64>,
+
+    // directory where the memo is also written to, it's only printed otherwise
+    #[clap(long, env)]
+    pub output_folder: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct HistoryArgs {
+    // only list the txs currently in this state
+    #[clap(long, env, value_parser = ["built", "signed", "submitted", "failed", "applied", "rejected", "expired"])]
+    pub status: Option<String>,
+
+    // tx label as recorded in the journal, e.g. "Bond" or "Transparent"
+    #[clap(long, env)]
+    pub kind: Option<String>,
+
+    // show a single tx with all its status changes
+    #[clap(long, env)]
+    pub tx_hash: Option<String>,
+
+    #[clap(long, env, default_value_t = 20)]
+    pub limit: usize,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ServeArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(long, env, default_value = "127.0.0.1:8080")]
+    pub listen: SocketAddr,
+
+    // expected as `Authorization: Bearer <token>` on every request
+    #[clap(long, env)]
+    #[arg(required = true)]
+    pub api_token: String,
+
+    #[clap(long, env, default_value = DEFAULT_MASP_INDEXER_URL)]
+    pub masp_indexer_url: String,
+
+    // wallet aliases of the spending keys synced by the daemon
+    #[clap(
+        long = "spending-key-alias",
+        env,
+        value_delimiter = ',',
+        default_value = "spending"
+    )]
+    pub spending_key_aliases: Vec<String>,
+
+    // seconds between two background shielded syncs, 0 disables them
+    #[clap(long, env, default_value_t = 60)]
+    pub sync_interval: u64,
+
+    // sync on every new block instead of on `--sync-interval`
+    #[clap(long, env)]
+    pub sync_on_block: bool,
+
+    // most transparent withdrawals sent in a single tx
+    #[clap(long, env, default_value_t = 10)]
+    pub withdrawal_batch_size: usize,
+
+    // seconds between two sweeps of the hot account, 0 disables them
+    #[clap(long, env, default_value_t = 0)]
+    pub sweep_interval: u64,
+
+    #[clap(flatten)]
+    pub sweep: SweepParams,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ApprovalsArgs {
+    #[clap(long, env, value_parser = ["pending", "approved", "rejected", "used"])]
+    pub status: Option<String>,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ApproveArgs {
+    #[arg(required = true)]
+    pub id: i64,
+
+    #[clap(long)]
+    pub reject: bool,
+}
+
+// the band the hot account balance is kept in, shared by `sweep` and `serve`
+#[derive(clap::Args, Clone, Debug)]
+pub struct SweepParams {
+    // address or wallet alias of the hot account
+    #[clap(long = "sweep-source", env = "SWEEP_SOURCE", default_value = "source")]
+    pub source: String,
+
+    #[clap(
+        long = "sweep-gas-payer",
+        env = "SWEEP_GAS_PAYER",
+        default_value = "source"
+    )]
+    pub gas_payer: String,
+
+    // transparent address, or payment address with `--sweep-shielded`
+    #[clap(long, env)]
+    pub cold_address: Option<String>,
+
+    #[clap(long = "sweep-token", env = "SWEEP_TOKEN", default_value = "nam")]
+    pub token: String,
+
+    // the balance above it is swept, only alerts are raised without it
+    #[clap(long, env)]
+    pub hot_max: Option<u64>,
+
+    // an alert is raised when the balance is below it
+    #[clap(long, env)]
+    pub hot_min: Option<u64>,
+
+    // shield the swept tokens to the cold payment address
+    #[clap(long = "sweep-shielded", env = "SWEEP_SHIELDED")]
+    pub shielded: bool,
+
+    // receives the alerts as a JSON POST, they are only logged otherwise
+    #[clap(long, env)]
+    pub alert_webhook: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct SweepArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    #[clap(flatten)]
+    pub sweep: SweepParams,
+
+    // keep sweeping every this many seconds instead of once
+    #[clap(long, env)]
+    pub interval: Option<u64>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct ReconcileArgs {
+    #[clap(flatten)]
+    pub node: NodeArgs,
+
+    // transparent addresses or wallet aliases
+    #[clap(long = "address", env = "RECONCILE_ADDRESSES", value_delimiter = ',')]
+    pub addresses: Vec<String>,
+
+    // extended viewing keys or wallet aliases
+    #[clap(
+        long = "viewing-key",
+        env = "RECONCILE_VIEWING_KEYS",
+        value_delimiter = ','
+    )]
+    pub viewing_keys: Vec<String>,
+
+    #[clap(
+        long = "token",
+        env = "RECONCILE_TOKENS",
+        value_delimiter = ',',
+        default_value = "nam"
+    )]
+    pub tokens: Vec<String>,
+
+    // sync the shielded context before reading the shielded balances
+    #[clap(long, env)]
+    pub sync: bool,
+
+    #[clap(long, env, default_value = DEFAULT_MASP_INDEXER_URL)]
+    pub masp_indexer_url: String,
+
+    // JSON file of the expected balance of each holding and token
+    #[clap(long, env)]
+    pub expected: Option<PathBuf>,
+
+    #[clap(long, env, value_enum, default_value = "table")]
+    pub output: OutputFormat,
 }
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use namada_sdk::{
+    rpc::{self, TxEventQuery, TxResponse},
+    time::DateTimeUtc,
+    tx::{data::ResultCode, Tx},
+    Namada,
+};
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::Client;
+
+use crate::sdk::Sdk;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(30);
+// upper bound for txs without an expiration in their header
+const MAX_WAIT: Duration = Duration::from_secs(600);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalStatus {
+    Applied,
+    Rejected,
+    Expired,
+}
+
+impl std::fmt::Display for FinalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FinalStatus::Applied => write!(f, "applied"),
+            FinalStatus::Rejected => write!(f, "rejected"),
+            FinalStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+fn is_expired(tx: &Tx, started: Instant) -> bool {
+    match tx.header().expiration {
+        Some(expiration) => DateTimeUtc::now() > expiration,
+        None => started.elapsed() > MAX_WAIT,
+    }
+}
+
+// the tx is broadcast again when a re-broadcast is accepted as new, it had been dropped from the
+// mempool of the node in between. A tx already included is refused by the replay protection of the
+// node's CheckTx, one still in its mempool cache with an rpc error from CometBFT, tracking goes on
+// in both cases
+pub async fn rebroadcast(sdk: &Sdk, tx: &Tx, wrapper_hash: &str) {
+    match sdk.namada.client().broadcast_tx_sync(tx.to_bytes()).await {
+        Ok(response) if response.code.is_ok() => {
+            tracing::warn!(
+                "Tx {} was dropped from the mempool, re-broadcast it",
+                wrapper_hash
+            )
+        }
+        Ok(response) if response.code.value() == u32::from(ResultCode::ReplayTx) => {
+            tracing::debug!("Tx {} was already included", wrapper_hash)
+        }
+        Ok(response) => tracing::warn!(
+            "Re-broadcast of tx {} refused: {}",
+            wrapper_hash,
+            response.log
+        ),
+        Err(e) => tracing::debug!(
+            "Re-broadcast of tx {} not accepted, it may still be in the mempool: {}",
+            wrapper_hash,
+            e
+        ),
+    }
+}
+
+// polls the node for the applied event of the wrapper until the tx is included or can't be anymore,
+// `None` means the tx expired
+pub async fn track(sdk: &Sdk, tx: &Tx) -> Result<Option<TxResponse>, String> {
+    let wrapper_hash = tx
+        .wrapper_hash()
+        .ok_or_else(|| "Only wrapper txs can be tracked".to_string())?
+        .to_string();
+    let started = Instant::now();
+    let mut last_broadcast = Instant::now();
+
+    tracing::info!("Waiting for tx {} to be included...", wrapper_hash);
+    loop {
+        match rpc::query_tx_events(sdk.namada.client(), TxEventQuery::Applied(&wrapper_hash)).await
+        {
+            Ok(Some(event)) => return Ok(Some(TxResponse::from_event(event))),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Can't query the status of tx {}: {}", wrapper_hash, e),
+        }
+
+        // a block could still include it right at the expiration, so the event is queried first
+        if is_expired(tx, started) {
+            return Ok(None);
+        }
+        if last_broadcast.elapsed() >= REBROADCAST_INTERVAL {
+            rebroadcast(sdk, tx, &wrapper_hash).await;
+            last_broadcast = Instant::now();
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
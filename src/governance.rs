@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, HashMap};
+
+use namada_sdk::{
+    address::Address,
+    governance::{
+        storage::{keys::get_counter_key, proposal::StorageProposal},
+        utils::ProposalStatus,
+    },
+    rpc, Namada,
+};
+use serde::Serialize;
+
+use crate::{
+    config::{
+        AppConfig, OutputFormat, ProposalArgs, ProposalStatusFilter, ProposalsArgs, VoteArgs,
+        VoteOption,
+    },
+    keystore::{self, PasswordSource},
+    pipeline,
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    vote::execute_vote_tx,
+};
+
+impl VoteOption {
+    // the string representation expected by the vote tx builder
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VoteOption::Yay => "yay",
+            VoteOption::Nay => "nay",
+            VoteOption::Abstain => "abstain",
+        }
+    }
+}
+
+pub async fn vote(sdk: &Sdk, config: &AppConfig, args: &VoteArgs) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let voter = keystore::find_address(sdk, &args.source).await?;
+
+    let proposal = rpc::query_proposal_by_id(&sdk.namada.clone_client(), args.proposal_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Proposal {} doesn't exist", args.proposal_id))?;
+    let epoch = rpc::query_epoch(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+    let is_validator = rpc::is_validator(&sdk.namada.clone_client(), &voter)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !proposal.can_be_voted(epoch, is_validator) {
+        return Err(format!(
+            "Proposal {} can't be voted{} at epoch {} (voting period from epoch {} to epoch {})",
+            args.proposal_id,
+            if is_validator { " by validators" } else { "" },
+            epoch,
+            proposal.voting_start_epoch,
+            proposal.voting_end_epoch
+        ));
+    }
+
+    // validators vote with their own stake, any other address needs delegations at the start of
+    // the voting period
+    if !is_validator {
+        let delegations = rpc::get_delegation_validators(
+            &sdk.namada.clone_client(),
+            &voter,
+            proposal.voting_start_epoch,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        if delegations.is_empty() {
+            return Err(format!(
+                "{} is neither a validator nor a delegator at epoch {}, it can't vote on proposal {}",
+                voter, proposal.voting_start_epoch, args.proposal_id
+            ));
+        }
+    }
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!(
+        "Voting {} on proposal {} as {}...",
+        args.vote.as_str(),
+        args.proposal_id,
+        voter
+    );
+    execute_vote_tx(
+        sdk,
+        voter,
+        args.proposal_id,
+        args.vote.as_str().to_string(),
+        gas_payer,
+        signers,
+        args.tx.memo.clone(),
+        args.tx.expiration_timestamp_utc,
+        args.tx.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("Vote executed!");
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct TallyReport {
+    pub result: String,
+    pub total_voting_power: String,
+    pub yay: String,
+    pub nay: String,
+    pub abstain: String,
+}
+
+#[derive(Serialize)]
+pub struct VoteReport {
+    pub alias: String,
+    pub voter: String,
+    pub validator: String,
+    pub vote: String,
+}
+
+#[derive(Serialize)]
+pub struct ProposalReport {
+    pub id: u64,
+    pub author: String,
+    pub kind: String,
+    pub status: String,
+    pub voting_start_epoch: u64,
+    pub voting_end_epoch: u64,
+    pub activation_epoch: u64,
+    pub content: BTreeMap<String, String>,
+    pub tally: Option<TallyReport>,
+    pub our_votes: Vec<VoteReport>,
+}
+
+impl ProposalStatusFilter {
+    fn matches(&self, status: &ProposalStatus) -> bool {
+        matches!(
+            (self, status),
+            (ProposalStatusFilter::Pending, ProposalStatus::Pending)
+                | (ProposalStatusFilter::Active, ProposalStatus::OnGoing)
+                | (ProposalStatusFilter::Ended, ProposalStatus::Ended)
+        )
+    }
+}
+
+// only queries, nothing is signed so the password isn't needed
+pub async fn proposals(sdk: &Sdk, args: &ProposalsArgs) -> Result<(), String> {
+    let client = sdk.namada.clone_client();
+    let epoch = rpc::query_epoch(&client).await.map_err(|e| e.to_string())?;
+    // the counter is the id the next proposal will get
+    let counter = rpc::query_storage_value::<_, u64>(&client, &get_counter_key())
+        .await
+        .map_err(|e| e.to_string())?;
+    let our_addresses = wallet_addresses(sdk).await;
+
+    let mut reports = vec![];
+    for proposal_id in (0..counter).rev() {
+        let Some(proposal) = rpc::query_proposal_by_id(&client, proposal_id)
+            .await
+            .map_err(|e| e.to_string())?
+        else {
+            continue;
+        };
+        let status = proposal.get_status(epoch);
+        if let Some(filter) = args.status {
+            if !filter.matches(&status) {
+                continue;
+            }
+        }
+        reports.push(query_proposal_report(sdk, proposal, status, &our_addresses).await?);
+    }
+
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_proposals_table(epoch.0, &reports),
+    }
+
+    Ok(())
+}
+
+pub async fn proposal(sdk: &Sdk, args: &ProposalArgs) -> Result<(), String> {
+    let client = sdk.namada.clone_client();
+    let epoch = rpc::query_epoch(&client).await.map_err(|e| e.to_string())?;
+    let proposal = rpc::query_proposal_by_id(&client, args.proposal_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Proposal {} doesn't exist", args.proposal_id))?;
+    let status = proposal.get_status(epoch);
+    let our_addresses = wallet_addresses(sdk).await;
+    let report = query_proposal_report(sdk, proposal, status, &our_addresses).await?;
+
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_proposal_details(&report),
+    }
+
+    Ok(())
+}
+
+async fn wallet_addresses(sdk: &Sdk) -> HashMap<Address, String> {
+    sdk.namada
+        .wallet
+        .read()
+        .await
+        .get_addresses()
+        .into_iter()
+        .map(|(alias, address)| (address, alias))
+        .collect()
+}
+
+// the tally is only known once the voting period started, votes cast by one of the wallet
+// addresses (as a validator or as a delegator) are reported as ours
+async fn query_proposal_report(
+    sdk: &Sdk,
+    proposal: StorageProposal,
+    status: ProposalStatus,
+    our_addresses: &HashMap<Address, String>,
+) -> Result<ProposalReport, String> {
+    let client = sdk.namada.clone_client();
+
+    let tally = match status {
+        ProposalStatus::Pending => None,
+        _ => rpc::query_proposal_result(&client, proposal.id)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|result| TallyReport {
+                result: result.result.to_string(),
+                total_voting_power: result.total_voting_power.to_string_native(),
+                yay: result.total_yay_power.to_string_native(),
+                nay: result.total_nay_power.to_string_native(),
+                abstain: result.total_abstain_power.to_string_native(),
+            }),
+    };
+
+    let our_votes = match status {
+        ProposalStatus::Pending => vec![],
+        _ => rpc::query_proposal_votes(&client, proposal.id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter_map(|vote| {
+                our_addresses.get(&vote.delegator).map(|alias| VoteReport {
+                    alias: alias.clone(),
+                    voter: vote.delegator.to_string(),
+                    validator: vote.validator.to_string(),
+                    vote: vote.data.to_string(),
+                })
+            })
+            .collect(),
+    };
+
+    Ok(ProposalReport {
+        id: proposal.id,
+        author: proposal.author.to_string(),
+        kind: proposal.r#type.to_string(),
+        status: status.to_string(),
+        voting_start_epoch: proposal.voting_start_epoch.0,
+        voting_end_epoch: proposal.voting_end_epoch.0,
+        activation_epoch: proposal.activation_epoch.0,
+        content: proposal.content.into_iter().collect(),
+        tally,
+        our_votes,
+    })
+}
+
+fn print_proposals_table(epoch: u64, reports: &[ProposalReport]) {
+    println!("Governance proposals at epoch {}", epoch);
+    if reports.is_empty() {
+        println!("No proposals");
+        return;
+    }
+
+    println!(
+        "{:>6} {:<10} {:<12} {:>15} {:>12} {:<24}",
+        "ID", "STATUS", "KIND", "VOTING EPOCHS", "RESULT", "OUR VOTES"
+    );
+    for report in reports {
+        let our_votes = report
+            .our_votes
+            .iter()
+            .map(|vote| format!("{}:{}", vote.alias, vote.vote))
+            .collect::<Vec<_>>();
+        println!(
+            "{:>6} {:<10} {:<12} {:>15} {:>12} {:<24}",
+            report.id,
+            report.status,
+            report.kind,
+            format!("{}-{}", report.voting_start_epoch, report.voting_end_epoch),
+            report
+                .tally
+                .as_ref()
+                .map(|tally| tally.result.as_str())
+                .unwrap_or("-"),
+            if our_votes.is_empty() {
+                "-".to_string()
+            } else {
+                our_votes.join(",")
+            }
+        );
+    }
+}
+
+fn print_proposal_details(report: &ProposalReport) {
+    println!("Proposal {} ({})", report.id, report.status);
+    println!("  author: {}", report.author);
+    println!("  kind: {}", report.kind);
+    println!(
+        "  voting from epoch {} to epoch {}, activation at epoch {}",
+        report.voting_start_epoch, report.voting_end_epoch, report.activation_epoch
+    );
+    for (key, value) in &report.content {
+        println!("  {}: {}", key, value);
+    }
+    match &report.tally {
+        Some(tally) => println!(
+            "  tally: {} (yay {}, nay {}, abstain {} out of {})",
+            tally.result, tally.yay, tally.nay, tally.abstain, tally.total_voting_power
+        ),
+        None => println!("  tally: not started"),
+    }
+    if report.our_votes.is_empty() {
+        println!("  our votes: none");
+    }
+    for vote in &report.our_votes {
+        println!(
+            "  our vote: {} ({}) voted {} through {}",
+            vote.alias, vote.voter, vote.vote, vote.validator
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{fs, str::FromStr};
+
+use namada_sdk::{
+    address::{Address, MASP},
+    args::{self, GenIbcShieldingTransfer, IbcShieldingTransferAsset, InputAmount, TxExpiration},
+    ibc::{
+        convert_masp_tx_to_ibc_memo,
+        core::host::types::identifiers::{ChannelId, PortId},
+    },
+    masp::{TransferSource, TransferTarget},
+    masp_primitives::zip32::{
+        ExtendedFullViewingKey, ExtendedSpendingKey as ExtendedSpendingKeyMasp, PseudoExtendedKey,
+    },
+    rpc,
+    time::DateTimeUtc,
+    token::{self, Amount, DenominatedAmount},
+    tx::gen_ibc_shielding_transfer,
+    Namada, PaymentAddress,
+};
+use tendermint_rpc::Url;
+
+use crate::{
+    config::{AppConfig, IbcShieldingMemoArgs, IbcTransferArgs},
+    ibc_transfer::{execute_ibc_transfer_tx, IbcPacketOptions},
+    keystore::{self, PasswordSource},
+    pipeline, policy,
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    tokens,
+};
+
+// the receiver lives on another chain so only the bech32 shape is checked, a Namada address is
+// refused since it would be a local transfer
+fn check_receiver(receiver: &str) -> Result<(), String> {
+    if Address::from_str(receiver).is_ok() {
+        return Err(format!(
+            "{} is a Namada address, use a transfer instead",
+            receiver
+        ));
+    }
+    match receiver.rsplit_once('1') {
+        Some((hrp, data)) if !hrp.is_empty() && data.len() >= 6 => Ok(()),
+        _ => Err(format!("{} is not a bech32 address", receiver)),
+    }
+}
+
+fn parse_refund_target(refund_target: &str) -> Result<TransferTarget, String> {
+    if let Ok(address) = Address::from_str(refund_target) {
+        return Ok(TransferTarget::Address(address));
+    }
+    PaymentAddress::from_str(refund_target)
+        .map(TransferTarget::PaymentAddress)
+        .map_err(|_| format!("Invalid refund target {}", refund_target))
+}
+
+pub async fn ibc_transfer(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &IbcTransferArgs,
+) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    check_receiver(&args.receiver)?;
+    let channel_id = ChannelId::from_str(&args.channel_id)
+        .map_err(|e| format!("Invalid channel id {}: {}", args.channel_id, e))?;
+    let port_id = PortId::from_str(&args.port_id)
+        .map_err(|e| format!("Invalid port id {}: {}", args.port_id, e))?;
+    let refund_target = args
+        .refund_target
+        .as_deref()
+        .map(parse_refund_target)
+        .transpose()?;
+
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) =
+        keystore::find_tx_signers(sdk, &args.tx, Some(password.clone())).await?;
+    let token = tokens::resolve_token(sdk, &config.base_dir(), &args.token).await?;
+    let token_name = tokens::token_display(sdk, &config.base_dir(), &token).await;
+
+    let (source, source_name) = if args.shielded {
+        let spending_key = keystore::find_spending_key(
+            sdk,
+            args.spending_key.as_deref(),
+            &args.spending_key_alias,
+            Some(password),
+        )
+        .await?;
+        let s_key_raw = ExtendedSpendingKeyMasp::from(spending_key);
+        (
+            TransferSource::ExtendedKey(PseudoExtendedKey::from(s_key_raw)),
+            args.spending_key_alias.clone(),
+        )
+    } else {
+        let source_address = keystore::find_address(sdk, &args.source).await?;
+
+        tracing::info!("Check {} {} balance...", source_address, token_name);
+        let balance =
+            rpc::get_token_balance(&sdk.namada.clone_client(), &token, &source_address, None)
+                .await
+                .unwrap_or_default();
+        if !balance.can_spend(&Amount::from_u64(args.amount)) {
+            return Err(format!(
+                "Not enough {} balance (got {}, neeeded {})",
+                token_name, balance, args.amount
+            ));
+        }
+        let source_name = source_address.to_string();
+        (TransferSource::Address(source_address), source_name)
+    };
+
+    let outgoing = policy::Outgoing {
+        kind: "IBC",
+        source: source_name,
+        target: args.receiver.clone(),
+        token: token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(sdk, &outgoing, args.tx.request_id.as_deref()).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        tracing::info!(
+            "Sending {} {} to {} over {}/{}...",
+            args.amount,
+            token_name,
+            args.receiver,
+            args.port_id,
+            args.channel_id
+        );
+        execute_ibc_transfer_tx(
+            sdk,
+            source,
+            args.receiver.clone(),
+            token,
+            gas_payer,
+            signers,
+            token::Amount::from_u64(args.amount),
+            IbcPacketOptions {
+                channel_id,
+                port_id,
+                timeout_height: args.timeout_height,
+                timeout_sec_offset: args.timeout_sec_offset,
+                refund_target,
+                ibc_memo: args.ibc_memo.clone(),
+            },
+            args.tx.memo.clone(),
+            args.tx.expiration_timestamp_utc,
+            args.tx.request_id.as_deref(),
+        )
+        .await
+    }
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
+    tracing::info!("IBC transfer executed, the tokens are escrowed until the packet is relayed");
+
+    Ok(())
+}
+
+// the payment address is either given or freshly derived from the spending key, the same way `run`
+// derives the one it shields to
+async fn find_payment_address(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &IbcShieldingMemoArgs,
+) -> Result<PaymentAddress, String> {
+    if let Some(payment_address) = &args.payment_address {
+        if let Ok(payment_address) = PaymentAddress::from_str(payment_address) {
+            return Ok(payment_address);
+        }
+        return sdk
+            .namada
+            .wallet
+            .read()
+            .await
+            .find_payment_addr(payment_address)
+            .cloned()
+            .ok_or_else(|| format!("Unknown payment address or alias {}", payment_address));
+    }
+
+    let password = match args.spending_key {
+        Some(_) => None,
+        None => Some(PasswordSource::from(&config.password).read(false)?),
+    };
+    let spending_key = keystore::find_spending_key(
+        sdk,
+        args.spending_key.as_deref(),
+        &args.spending_key_alias,
+        password,
+    )
+    .await?;
+    let extended_viewing_key =
+        ExtendedFullViewingKey::from(&ExtendedSpendingKeyMasp::from(spending_key));
+    Ok(keystore::gen_payment_address(&extended_viewing_key.fvk.vk))
+}
+
+// the sender includes the memo in its ICS-20 packet, the tokens are then shielded on receipt
+pub async fn ibc_shielding_memo(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &IbcShieldingMemoArgs,
+) -> Result<(), String> {
+    let channel_id = ChannelId::from_str(&args.channel_id)
+        .map_err(|e| format!("Invalid channel id {}: {}", args.channel_id, e))?;
+    let port_id = PortId::from_str(&args.port_id)
+        .map_err(|e| format!("Invalid port id {}: {}", args.port_id, e))?;
+    let payment_address = find_payment_address(sdk, config, args).await?;
+
+    // a token unknown to Namada is the denom on the sending chain, its Namada address is derived
+    // from the channel it's received on
+    let asset = match tokens::find_token(sdk, &config.base_dir(), &args.token).await? {
+        Some(token) => IbcShieldingTransferAsset::Address(token),
+        None => IbcShieldingTransferAsset::LookupNamadaAddress {
+            token: args.token.clone(),
+            port_id,
+            channel_id,
+        },
+    };
+    let expiration = match args.expiration_timestamp_utc {
+        Some(expiration) => TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration)
+                .ok_or_else(|| format!("Invalid expiration timestamp {}", expiration))?,
+        ),
+        None => TxExpiration::Default,
+    };
+
+    let gen_args = GenIbcShieldingTransfer {
+        query: args::Query {
+            ledger_address: Url::from_str(sdk.namada.client().current_url())
+                .map_err(|e| e.to_string())?,
+        },
+        output_folder: None,
+        target: payment_address,
+        asset,
+        amount: InputAmount::Unvalidated(DenominatedAmount::native(Amount::from_u64(args.amount))),
+        expiration,
+    };
+
+    tracing::info!(
+        "Generating the shielding memo of {} {} to {} over {}/{}...",
+        args.amount,
+        args.token,
+        payment_address,
+        args.port_id,
+        args.channel_id
+    );
+    let masp_tx = gen_ibc_shielding_transfer(&sdk.namada, gen_args)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No shielding data was generated".to_string())?;
+    let memo = convert_masp_tx_to_ibc_memo(&masp_tx);
+
+    if let Some(output_folder) = &args.output_folder {
+        let path = output_folder.join(format!("ibc_shielding_memo_{}", masp_tx.txid()));
+        fs::write(&path, &memo)
+            .map_err(|e| format!("Can't write memo file {}: {}", path.display(), e))?;
+        tracing::info!("Memo written to {}", path.display());
+    }
+    // the packet is addressed to the MASP, the payment address only appears in the memo
+    tracing::info!("Packet receiver: {}", MASP);
+    println!("{}", memo);
+
+    Ok(())
+}
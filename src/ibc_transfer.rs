@@ -0,0 +1,116 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, InputAmount, TxBuilder, TxExpiration},
+    ibc::core::host::types::identifiers::{ChannelId, PortId},
+    key::common,
+    masp::{TransferSource, TransferTarget},
+    masp_primitives::transaction::components::sapling::builder::RngBuildParams,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    token::{self, DenominatedAmount},
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+use rand_core::OsRng;
+
+use crate::{pipeline, sdk::Sdk};
+
+// the packet related options of an outbound transfer
+pub struct IbcPacketOptions {
+    pub channel_id: ChannelId,
+    pub port_id: PortId,
+    pub timeout_height: Option<u64>,
+    pub timeout_sec_offset: Option<u64>,
+    pub refund_target: Option<TransferTarget>,
+    pub ibc_memo: Option<String>,
+}
+
+pub async fn build_ibc_transfer_tx(
+    sdk: &Sdk,
+    source: TransferSource,
+    receiver: String,
+    token_address: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    packet: IbcPacketOptions,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut bparams = RngBuildParams::new(OsRng);
+
+    let mut transfer_tx_builder = sdk
+        .namada
+        .new_ibc_transfer(
+            source,
+            receiver,
+            token_address,
+            InputAmount::Unvalidated(DenominatedAmount::native(amount)),
+            packet.channel_id,
+        )
+        .port_id(packet.port_id);
+    if let Some(timeout_height) = packet.timeout_height {
+        transfer_tx_builder = transfer_tx_builder.timeout_height(timeout_height);
+    }
+    if let Some(timeout_sec_offset) = packet.timeout_sec_offset {
+        transfer_tx_builder = transfer_tx_builder.timeout_sec_offset(timeout_sec_offset);
+    }
+    if let Some(refund_target) = packet.refund_target {
+        transfer_tx_builder = transfer_tx_builder.refund_target(refund_target);
+    }
+    if let Some(ibc_memo) = packet.ibc_memo {
+        transfer_tx_builder = transfer_tx_builder.ibc_memo(ibc_memo);
+    }
+    transfer_tx_builder = transfer_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    transfer_tx_builder = transfer_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        transfer_tx_builder = transfer_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        transfer_tx_builder = transfer_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    transfer_tx_builder = transfer_tx_builder.signing_keys(signers);
+
+    let (transfer_tx, signing_data, _masp_epoch) = transfer_tx_builder
+        .build(&sdk.namada, &mut bparams)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((transfer_tx, signing_data, transfer_tx_builder.tx))
+}
+
+pub async fn execute_ibc_transfer_tx(
+    sdk: &Sdk,
+    source: TransferSource,
+    receiver: String,
+    token_address: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    packet: IbcPacketOptions,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut transfer_tx, signing_data, tx_args) = build_ibc_transfer_tx(
+        sdk,
+        source,
+        receiver,
+        token_address,
+        gas_payer,
+        signers,
+        amount,
+        packet,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(sdk, &mut transfer_tx, &tx_args, signing_data, "IBC").await?;
+
+    pipeline::submit_tx(sdk, transfer_tx, &tx_args, "IBC", request_id)
+        .await
+        .map(|_| true)
+}
@@ -0,0 +1,285 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use namada_sdk::time::DateTimeUtc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::config::{AppConfig, HistoryArgs, OutputFormat};
+
+// every tx goes through these states in order before reaching one of the final statuses (applied,
+// rejected or expired), `failed` is recorded when signing or broadcasting errors out locally
+pub const BUILT: &str = "built";
+pub const SIGNED: &str = "signed";
+pub const SUBMITTED: &str = "submitted";
+pub const FAILED: &str = "failed";
+
+#[derive(Serialize)]
+pub struct JournalTx {
+    pub wrapper_hash: String,
+    pub kind: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Serialize)]
+pub struct JournalEvent {
+    pub status: String,
+    pub error: Option<String>,
+    pub at: i64,
+}
+
+// `journal.sqlite` under the base dir: one row per tx with its current status and one row per
+// status transition
+pub struct Journal {
+    conn: Connection,
+}
+
+fn read_tx(row: &Row<'_>) -> rusqlite::Result<JournalTx> {
+    Ok(JournalTx {
+        wrapper_hash: row.get(0)?,
+        kind: row.get(1)?,
+        status: row.get(2)?,
+        error: row.get(3)?,
+        request_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl Journal {
+    pub fn open(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("journal.sqlite");
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Can't open journal {}: {}", path.display(), e))?;
+        conn.busy_timeout(Duration::from_secs(10))
+            .and_then(|_| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS txs (
+                wrapper_hash TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT,
+                request_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS tx_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                wrapper_hash TEXT NOT NULL REFERENCES txs(wrapper_hash),
+                status TEXT NOT NULL,
+                error TEXT,
+                at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS tx_events_hash ON tx_events(wrapper_hash);",
+                )
+            })
+            .map_err(|e| format!("Can't initialize journal {}: {}", path.display(), e))?;
+        Ok(Self { conn })
+    }
+
+    // the tx and its event are written together
+    pub fn record(
+        &mut self,
+        wrapper_hash: &str,
+        kind: &str,
+        status: &str,
+        error: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Result<(), String> {
+        let at = now();
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Can't write to the journal: {}", e))?;
+        tx.execute(
+            "INSERT INTO txs (wrapper_hash, kind, status, error, request_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            ON CONFLICT(wrapper_hash) DO UPDATE SET
+                status = excluded.status,
+                error = excluded.error,
+                request_id = COALESCE(excluded.request_id, txs.request_id),
+                updated_at = excluded.updated_at",
+            params![wrapper_hash, kind, status, error, request_id, at],
+        )
+        .and_then(|_| {
+            tx.execute(
+                "INSERT INTO tx_events (wrapper_hash, status, error, at) VALUES (?1, ?2, ?3, ?4)",
+                params![wrapper_hash, status, error, at],
+            )
+        })
+        .and_then(|_| tx.commit())
+        .map_err(|e| format!("Can't write to the journal: {}", e))
+    }
+
+    // the most recent first, optionally filtered on the current status and the kind
+    pub fn list(
+        &self,
+        status: Option<&str>,
+        kind: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<JournalTx>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT wrapper_hash, kind, status, error, request_id, created_at, updated_at
+                FROM txs
+                WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR kind = ?2)
+                ORDER BY created_at DESC
+                LIMIT ?3",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![status, kind, limit as i64], read_tx)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    pub fn get(&self, wrapper_hash: &str) -> Result<Option<JournalTx>, String> {
+        self.conn
+            .query_row(
+                "SELECT wrapper_hash, kind, status, error, request_id, created_at, updated_at
+                FROM txs WHERE wrapper_hash = ?1",
+                params![wrapper_hash],
+                read_tx,
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn events(&self, wrapper_hash: &str) -> Result<Vec<JournalEvent>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT status, error, at FROM tx_events WHERE wrapper_hash = ?1 ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![wrapper_hash], |row| {
+                Ok(JournalEvent {
+                    status: row.get(0)?,
+                    error: row.get(1)?,
+                    at: row.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    // the tx with all its status changes, hashes are matched case insensitively
+    pub fn history(&self, wrapper_hash: &str) -> Result<Option<TxHistory>, String> {
+        let wrapper_hash = wrapper_hash.to_lowercase();
+        let Some(tx) = self.get(&wrapper_hash)? else {
+            return Ok(None);
+        };
+        let events = self.events(&wrapper_hash)?;
+        Ok(Some(TxHistory { tx, events }))
+    }
+}
+
+// the journal is a record of what happened, failing to write it doesn't fail the tx
+pub fn record(
+    base_dir: &Path,
+    wrapper_hash: &str,
+    kind: &str,
+    status: &str,
+    error: Option<&str>,
+    request_id: Option<&str>,
+) {
+    if let Err(e) = Journal::open(base_dir)
+        .and_then(|mut journal| journal.record(wrapper_hash, kind, status, error, request_id))
+    {
+        tracing::warn!("{}", e);
+    }
+}
+
+#[derive(Serialize)]
+pub struct TxHistory {
+    #[serde(flatten)]
+    pub tx: JournalTx,
+    pub events: Vec<JournalEvent>,
+}
+
+fn format_time(at: i64) -> String {
+    DateTimeUtc::from_unix_timestamp(at)
+        .map(|time| time.to_string())
+        .unwrap_or_else(|| at.to_string())
+}
+
+pub fn history(config: &AppConfig, args: &HistoryArgs) -> Result<(), String> {
+    let journal = Journal::open(&config.base_dir())?;
+
+    if let Some(tx_hash) = &args.tx_hash {
+        let history = journal
+            .history(tx_hash)?
+            .ok_or_else(|| format!("Tx {} is not in the journal", tx_hash))?;
+        match args.output {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&history).map_err(|e| e.to_string())?
+            ),
+            OutputFormat::Table => print_tx_history(&history),
+        }
+        return Ok(());
+    }
+
+    let txs = journal.list(args.status.as_deref(), args.kind.as_deref(), args.limit)?;
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&txs).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_txs_table(&txs),
+    }
+
+    Ok(())
+}
+
+fn print_txs_table(txs: &[JournalTx]) {
+    if txs.is_empty() {
+        println!("No txs");
+        return;
+    }
+
+    println!(
+        "{:<64} {:<24} {:<10} {:<20} {:<20}",
+        "WRAPPER HASH", "KIND", "STATUS", "UPDATED", "REQUEST ID"
+    );
+    for tx in txs {
+        println!(
+            "{:<64} {:<24} {:<10} {:<20} {:<20}",
+            tx.wrapper_hash,
+            tx.kind,
+            tx.status,
+            format_time(tx.updated_at),
+            tx.request_id.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+fn print_tx_history(history: &TxHistory) {
+    let tx = &history.tx;
+    println!("{} tx {} ({})", tx.kind, tx.wrapper_hash, tx.status);
+    if let Some(request_id) = &tx.request_id {
+        println!("  request id: {}", request_id);
+    }
+    if let Some(error) = &tx.error {
+        println!("  error: {}", error);
+    }
+    for event in &history.events {
+        match &event.error {
+            Some(error) => println!("  {} {}: {}", format_time(event.at), event.status, error),
+            None => println!("  {} {}", format_time(event.at), event.status),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use namada_sdk::{
+    address::Address,
+    key::SchemeType,
+    masp_primitives::zip32::{
+        ExtendedFullViewingKey, ExtendedSpendingKey as ExtendedSpendingKeyMasp,
+    },
+    wallet::gen_secret_key,
+    ExtendedSpendingKey, ExtendedViewingKey, PaymentAddress,
+};
+use rand_core::{OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{AppConfig, KeygenArgs},
+    keystore::{self, PasswordSource},
+};
+
+// keys that get an alias are stored encrypted, the others are printed once on stdout and never persisted
+pub fn keygen(config: &AppConfig, args: &KeygenArgs) -> Result<(), String> {
+    let base_dir = config.base_dir();
+    let mut wallet = keystore::load_wallet(&base_dir);
+    let password = if args.alias.is_some() || args.spending_key_alias.is_some() {
+        Some(PasswordSource::from(&config.password).read(true)?)
+    } else {
+        None
+    };
+
+    if args.kind.transparent {
+        let sk = gen_secret_key(SchemeType::from(args.scheme), &mut OsRng);
+        let public_key = sk.to_public();
+        let address = Address::from(&public_key);
+
+        tracing::info!("Public key: {}", public_key);
+        tracing::info!("Implicit address: {}", address);
+
+        match &args.alias {
+            Some(alias) => {
+                wallet
+                    .insert_keypair(
+                        alias.clone(),
+                        args.force,
+                        sk,
+                        password.clone(),
+                        Some(address),
+                        None,
+                    )
+                    .ok_or_else(|| format!("Alias {} is already used in the wallet", alias))?;
+                tracing::info!("Secret key stored as {}", alias);
+            }
+            None => println!("{}", sk),
+        }
+    }
+
+    if args.kind.shielded {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(seed.as_mut());
+        let s_key_raw = ExtendedSpendingKeyMasp::master(seed.as_ref());
+
+        let extended_viewing_key = ExtendedFullViewingKey::from(&s_key_raw);
+        let (_div, payment_addr) = extended_viewing_key.default_address();
+        let viewing_key = ExtendedViewingKey::from(extended_viewing_key);
+        let payment_addr = PaymentAddress::from(payment_addr);
+        let spending_key = ExtendedSpendingKey::from(s_key_raw);
+
+        tracing::info!("Viewing key: {}", viewing_key);
+        tracing::info!("Default payment address: {}", payment_addr);
+
+        match &args.spending_key_alias {
+            Some(alias) => {
+                wallet
+                    .insert_spending_key(
+                        alias.clone(),
+                        args.force,
+                        spending_key,
+                        password.clone(),
+                        args.birthday.map(Into::into),
+                        None,
+                    )
+                    .ok_or_else(|| format!("Alias {} is already used in the wallet", alias))?;
+                let payment_addr_alias = format!("{}-addr", alias);
+                wallet
+                    .insert_payment_addr(payment_addr_alias.clone(), payment_addr, args.force)
+                    .ok_or_else(|| {
+                        format!("Alias {} is already used in the wallet", payment_addr_alias)
+                    })?;
+                tracing::info!(
+                    "Spending key stored as {}, payment address as {}",
+                    alias,
+                    payment_addr_alias
+                );
+            }
+            None => println!("{}", spending_key),
+        }
+    }
+
+    if password.is_some() {
+        wallet.save().map_err(|e| e.to_string())?;
+        tracing::info!("Wallet saved to {}", base_dir.join("wallet.toml").display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use namada_sdk::{
+    address::Address,
+    key::common::{PublicKey, SecretKey},
+    masp::find_valid_diversifier,
+    masp_primitives::{sapling::ViewingKey, zip32::ExtendedFullViewingKey},
+    wallet::{fs::FsWalletUtils, FindKeyError, Wallet},
+    ExtendedSpendingKey, ExtendedViewingKey, PaymentAddress,
+};
+use rand_core::OsRng;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{AppConfig, ImportKeyArgs, PasswordArgs, TxSigningArgs},
+    sdk::Sdk,
+};
+
+pub enum PasswordSource {
+    Prompt,
+    File(PathBuf),
+    Env(String),
+}
+
+impl From<&PasswordArgs> for PasswordSource {
+    fn from(args: &PasswordArgs) -> Self {
+        match (&args.password_file, &args.password_env) {
+            (Some(path), _) => PasswordSource::File(path.clone()),
+            (None, Some(var)) => PasswordSource::Env(var.clone()),
+            (None, None) => PasswordSource::Prompt,
+        }
+    }
+}
+
+impl PasswordSource {
+    // when `confirm` is set the tty prompt asks twice, used when encrypting new keys
+    pub fn read(&self, confirm: bool) -> Result<Zeroizing<String>, String> {
+        let password = match self {
+            PasswordSource::Prompt => {
+                let password = Zeroizing::new(
+                    rpassword::prompt_password("Wallet password: ").map_err(|e| e.to_string())?,
+                );
+                if confirm {
+                    let repeated = Zeroizing::new(
+                        rpassword::prompt_password("Repeat wallet password: ")
+                            .map_err(|e| e.to_string())?,
+                    );
+                    if password != repeated {
+                        return Err("Passwords do not match".to_string());
+                    }
+                }
+                password
+            }
+            PasswordSource::File(path) => Zeroizing::new(
+                fs::read_to_string(path)
+                    .map_err(|e| format!("Can't read password file {}: {}", path.display(), e))?
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            ),
+            PasswordSource::Env(var) => Zeroizing::new(
+                env::var(var).map_err(|e| format!("Can't read password from ${}: {}", var, e))?,
+            ),
+        };
+
+        if password.is_empty() {
+            return Err("Wallet password can't be empty".to_string());
+        }
+        Ok(password)
+    }
+}
+
+pub fn load_wallet(base_dir: &Path) -> Wallet<FsWalletUtils> {
+    let mut wallet = FsWalletUtils::new(base_dir.to_path_buf());
+    if base_dir.join("wallet.toml").exists() {
+        wallet.load().expect("Should be able to load the wallet;");
+    }
+    wallet
+}
+
+// stores the key encrypted under `alias` and writes `wallet.toml` back to disk
+pub fn import_key(config: &AppConfig, args: &ImportKeyArgs) -> Result<(), String> {
+    let base_dir = config.base_dir();
+    let mut wallet = load_wallet(&base_dir);
+    let password = PasswordSource::from(&config.password).read(true)?;
+
+    if let Some(secret_key) = &args.key.secret_key {
+        let sk = SecretKey::from_str(secret_key).map_err(|e| e.to_string())?;
+        let address = Address::from(&sk.to_public());
+        wallet
+            .insert_keypair(
+                args.alias.clone(),
+                args.force,
+                sk,
+                Some(password),
+                Some(address.clone()),
+                None,
+            )
+            .ok_or_else(|| format!("Alias {} is already used in the wallet", args.alias))?;
+        tracing::info!("Imported secret key for {} as {}", address, args.alias);
+    } else if let Some(spending_key) = &args.key.spending_key {
+        let spending_key =
+            ExtendedSpendingKey::from_str(spending_key).map_err(|e| e.to_string())?;
+        wallet
+            .insert_spending_key(
+                args.alias.clone(),
+                args.force,
+                spending_key,
+                Some(password),
+                args.birthday.map(Into::into),
+                None,
+            )
+            .ok_or_else(|| format!("Alias {} is already used in the wallet", args.alias))?;
+        tracing::info!("Imported spending key as {}", args.alias);
+    }
+
+    wallet.save().map_err(|e| e.to_string())?;
+    tracing::info!("Wallet saved to {}", base_dir.join("wallet.toml").display());
+
+    Ok(())
+}
+
+// a raw key is only kept in memory, otherwise it's decrypted from the wallet (which caches it for signing)
+pub async fn find_secret_key(
+    sdk: &Sdk,
+    raw: Option<&str>,
+    alias: &str,
+    password: Option<Zeroizing<String>>,
+) -> Result<SecretKey, String> {
+    let mut wallet = sdk.namada.wallet.write().await;
+    match raw {
+        Some(raw) => {
+            let sk = SecretKey::from_str(raw).map_err(|e| e.to_string())?;
+            let address = Address::from(&sk.to_public());
+            wallet
+                .insert_keypair(
+                    alias.to_string(),
+                    true,
+                    sk.clone(),
+                    None,
+                    Some(address),
+                    None,
+                )
+                .ok_or_else(|| format!("Can't insert key {} in the wallet", alias))?;
+            Ok(sk)
+        }
+        None => wallet
+            .find_secret_key(alias, password)
+            .map_err(|e| format!("Can't find secret key {}: {}", alias, e)),
+    }
+}
+
+pub async fn find_spending_key(
+    sdk: &Sdk,
+    raw: Option<&str>,
+    alias: &str,
+    password: Option<Zeroizing<String>>,
+) -> Result<ExtendedSpendingKey, String> {
+    match raw {
+        Some(raw) => ExtendedSpendingKey::from_str(raw).map_err(|e| e.to_string()),
+        None => sdk
+            .namada
+            .wallet
+            .write()
+            .await
+            .find_spending_key(alias, password)
+            .map_err(|e| format!("Can't find spending key {}: {}", alias, e)),
+    }
+}
+
+// accepts either a bech32 extended viewing key or a wallet alias
+pub async fn find_viewing_key(sdk: &Sdk, key_or_alias: &str) -> Result<ViewingKey, String> {
+    let viewing_key = match ExtendedViewingKey::from_str(key_or_alias) {
+        Ok(viewing_key) => viewing_key,
+        Err(_) => *sdk
+            .namada
+            .wallet
+            .read()
+            .await
+            .find_viewing_key(key_or_alias)
+            .map_err(|e| format!("Can't find viewing key {}: {}", key_or_alias, e))?,
+    };
+    Ok(ExtendedFullViewingKey::from(viewing_key).fvk.vk)
+}
+
+// a fresh diversified payment address of the viewing key, each call returns a different one
+pub fn gen_payment_address(viewing_key: &ViewingKey) -> PaymentAddress {
+    let (div, _g_d) = find_valid_diversifier(&mut OsRng);
+    viewing_key
+        .to_payment_address(div)
+        .expect("a PaymentAddress")
+        .into()
+}
+
+// accepts either a bech32 address or a wallet alias
+pub async fn find_address(sdk: &Sdk, address_or_alias: &str) -> Result<Address, String> {
+    if let Ok(address) = Address::from_str(address_or_alias) {
+        return Ok(address);
+    }
+    sdk.namada
+        .wallet
+        .read()
+        .await
+        .find_address(address_or_alias)
+        .map(|address| address.into_owned())
+        .ok_or_else(|| format!("Unknown address or alias {}", address_or_alias))
+}
+
+// accepts either a public key or the wallet alias of a keypair
+pub async fn find_public_key(sdk: &Sdk, key_or_alias: &str) -> Result<PublicKey, String> {
+    if let Ok(public_key) = PublicKey::from_str(key_or_alias) {
+        return Ok(public_key);
+    }
+    sdk.namada
+        .wallet
+        .read()
+        .await
+        .find_public_key(key_or_alias)
+        .map_err(|e| format!("Can't find public key {}: {}", key_or_alias, e))
+}
+
+// decrypts each signer so that the wallet can sign with all of them, keys the wallet only knows
+// the public part of are left to the external signer
+pub async fn find_signers(
+    sdk: &Sdk,
+    aliases: &[String],
+    password: Option<Zeroizing<String>>,
+) -> Result<Vec<PublicKey>, String> {
+    let mut signers = Vec::with_capacity(aliases.len());
+    for alias in aliases {
+        let secret_key = sdk
+            .namada
+            .wallet
+            .write()
+            .await
+            .find_secret_key(alias, password.clone());
+        let public_key = match secret_key {
+            Ok(sk) => sk.to_public(),
+            Err(FindKeyError::KeyNotFound(_)) => find_public_key(sdk, alias).await?,
+            Err(e) => return Err(format!("Can't find secret key {}: {}", alias, e)),
+        };
+        signers.push(public_key);
+    }
+    Ok(signers)
+}
+
+// returns the fee payer and the signers, which default to the fee payer alone
+pub async fn find_tx_signers(
+    sdk: &Sdk,
+    args: &TxSigningArgs,
+    password: Option<Zeroizing<String>>,
+) -> Result<(PublicKey, Vec<PublicKey>), String> {
+    let gas_payer = find_signers(sdk, &[args.gas_payer.clone()], password.clone())
+        .await?
+        .remove(0);
+    let signers = if args.signers.is_empty() {
+        vec![gas_payer.clone()]
+    } else {
+        find_signers(sdk, &args.signers, password).await?
+    };
+    Ok((gas_payer, signers))
+}
@@ -0,0 +1,37 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+// txs spending from the same transparent account or the same spending key are never built
+// concurrently: shielded notes could be picked twice and transparent balances checked twice
+pub fn transparent_lane(source: impl Display) -> String {
+    format!("transparent/{}", source)
+}
+
+pub fn shielded_lane(spending_key_alias: &str) -> String {
+    format!("shielded/{}", spending_key_alias)
+}
+
+// one lock per lane, shared by the daemon handlers, the withdrawal queue and the sweep job
+#[derive(Default)]
+pub struct Lanes {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl Lanes {
+    // held while building and submitting a tx of the lane
+    pub async fn lock(&self, lane: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(lane.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
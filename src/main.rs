@@ -1,42 +1,66 @@
-use std::{env, str::FromStr, time::Duration};
+use std::{str::FromStr, time::Duration};
 
 use clap::Parser;
-use config::AppConfig;
+use config::{AppConfig, Command, NodeArgs, RunArgs};
+use keystore::PasswordSource;
 use namada_sdk::{
     address::Address,
     control_flow::install_shutdown_signal,
-    io::{DevNullProgressBar, NullIo},
-    key::common::SecretKey,
-    masp::{
-        find_valid_diversifier, fs::FsShieldedUtils, IndexerMaspClient, MaspLocalTaskEnv,
-        ShieldedContext, ShieldedSyncConfig,
-    },
+    io::NullIo,
+    masp::{fs::FsShieldedUtils, ShieldedContext},
     masp_primitives::zip32::{
         ExtendedFullViewingKey, ExtendedSpendingKey as ExtendedSpendingKeyMasp, PseudoExtendedKey,
     },
     rpc,
     token::{self, Amount},
-    wallet::{fs::FsWalletUtils, DatedKeypair},
-    ExtendedSpendingKey, Namada,
+    Namada,
 };
-use rand_core::OsRng;
-use reqwest::Url as reqUrl;
-use reveal_pk::execute_reveal_pk;
+use node::FailoverClient;
+use reveal_pk::reveal_pk_if_needed;
 use sdk::Sdk;
 use shielding_transfer::execute_shielding_tx;
-use tendermint_rpc::{HttpClient, Url};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 use transparent_transfer::execute_transparent_tx;
 use unshielding_transfer::execute_unshielding_tx;
 
+pub mod bond;
+pub mod claim_rewards;
 pub mod config;
+pub mod confirm;
+pub mod governance;
+pub mod ibc;
+pub mod ibc_transfer;
+pub mod journal;
+pub mod keygen;
+pub mod keystore;
+pub mod lanes;
+pub mod mnemonic;
+pub mod multisig;
+pub mod node;
+pub mod offline;
+pub mod pipeline;
+pub mod policy;
+pub mod reconcile;
+pub mod redelegate;
+pub mod requests;
 pub mod reveal_pk;
 pub mod sdk;
+pub mod serve;
+pub mod shielded;
 pub mod shielding_transfer;
+pub mod signer;
+pub mod staking;
+pub mod staking_report;
+pub mod sweep;
+pub mod tokens;
 pub mod transparent_transfer;
+pub mod unbond;
 pub mod unshielding_transfer;
 pub mod utils;
+pub mod vote;
+pub mod withdraw;
+pub mod withdrawals;
 
 #[tokio::main]
 async fn main() {
@@ -54,44 +78,176 @@ async fn main() {
         .with_ansi(false)
         .init();
 
-    let url = Url::from_str(&config.rpc).expect("invalid RPC address");
-    let http_client = HttpClient::new(url).unwrap();
+    if let Err(e) = dispatch(&config).await {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    }
+}
 
-    // we initialize a Sdk structure
-    let sdk = loop {
-        let base_dir = config
-            .base_dir
-            .clone()
-            .unwrap_or(env::current_dir().unwrap());
-        // Setup wallet storage
-        let mut wallet = FsWalletUtils::new(base_dir.clone());
-        if base_dir.join("wallet.toml").exists() {
-            wallet.load().expect("Should be able to load the wallet;");
+async fn dispatch(config: &AppConfig) -> Result<(), String> {
+    match &config.command {
+        Command::Run(args) => run(config, args).await,
+        Command::ImportKey(args) => keystore::import_key(config, args),
+        Command::GenMnemonic(args) => mnemonic::gen_mnemonic(args),
+        Command::DeriveKey(args) => mnemonic::derive_key(config, args),
+        Command::Keygen(args) => keygen::keygen(config, args),
+        Command::InitAccount(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            multisig::init_account(&sdk, config, args).await
+        }
+        Command::UpdateAccount(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            multisig::update_account(&sdk, config, args).await
+        }
+        Command::MultisigTransfer(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            multisig::multisig_transfer(&sdk, config, args).await
+        }
+        Command::Build(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            offline::build(&sdk, config, args).await
+        }
+        Command::Sign(args) => offline::sign(config, args),
+        Command::Submit(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            offline::submit(&sdk, args).await
+        }
+        Command::Bond(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking::bond(&sdk, config, args).await
+        }
+        Command::Unbond(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking::unbond(&sdk, config, args).await
+        }
+        Command::Withdraw(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking::withdraw(&sdk, config, args).await
+        }
+        Command::Redelegate(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking::redelegate(&sdk, config, args).await
+        }
+        Command::ClaimRewards(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking::claim_rewards(&sdk, config, args).await
+        }
+        Command::StakingReport(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            staking_report::staking_report(&sdk, args).await
+        }
+        Command::Vote(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            governance::vote(&sdk, config, args).await
+        }
+        Command::Proposals(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            governance::proposals(&sdk, args).await
+        }
+        Command::Proposal(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            governance::proposal(&sdk, args).await
+        }
+        Command::IbcTransfer(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            ibc::ibc_transfer(&sdk, config, args).await
+        }
+        Command::IbcShieldingMemo(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            ibc::ibc_shielding_memo(&sdk, config, args).await
+        }
+        Command::History(args) => journal::history(config, args),
+        Command::Approvals(args) => policy::approvals(config, args),
+        Command::Approve(args) => policy::approve(config, args),
+        Command::Sweep(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            sweep::sweep_command(&sdk, config, args).await
+        }
+        Command::Reconcile(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            reconcile::reconcile(&sdk, args).await
         }
+        Command::Serve(args) => {
+            let sdk = build_sdk(config, &args.node).await?;
+            serve::serve(sdk, config, args).await
+        }
+    }
+}
+
+// the node can be briefly unavailable right after the health checks, so building the sdk is
+// retried a bounded number of times
+async fn build_sdk(config: &AppConfig, node: &NodeArgs) -> Result<Sdk, String> {
+    let client = FailoverClient::connect(node).await?;
+    if let Some(native_token) = &node.native_token {
+        client.check_native_token(native_token).await?;
+    }
+    let base_dir = config.base_dir();
+
+    let mut attempt = 1;
+    loop {
+        // Setup wallet storage
+        let wallet = keystore::load_wallet(&base_dir);
 
         let mut shielded_ctx = ShieldedContext::new(FsShieldedUtils::new(base_dir.clone()));
         if base_dir.join("shielded.dat").exists() {
             shielded_ctx
                 .load()
                 .await
-                .expect("Should be able to load shielded context");
+                .map_err(|e| format!("Can't load the shielded context: {}", e))?;
         }
 
         let io = NullIo;
-
-        match Sdk::new(&config, http_client.clone(), wallet, shielded_ctx, io).await {
-            Ok(sdk) => break sdk,
-            Err(_) => std::thread::sleep(Duration::from_secs(2)),
+        let signer = signer::from_args(&config.signer)?;
+
+        match Sdk::new(
+            base_dir.clone(),
+            client.clone(),
+            wallet,
+            shielded_ctx,
+            io,
+            signer,
+        )
+        .await
+        {
+            Ok(sdk) => break Ok(sdk),
+            Err(e) if attempt < node.rpc_max_attempts => {
+                tracing::warn!(
+                    "Can't initialize the sdk (attempt {}/{}): {}",
+                    attempt,
+                    node.rpc_max_attempts,
+                    e
+                );
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                attempt += 1;
+            }
+            Err(e) => break Err(format!("Can't initialize the sdk: {}", e)),
         };
-    };
+    }
+}
+
+async fn run(config: &AppConfig, args: &RunArgs) -> Result<(), String> {
+    let sdk = build_sdk(config, &args.node).await?;
 
     let native_token = rpc::query_native_token(&sdk.namada.clone_client())
         .await
         .unwrap();
 
+    // keys that are not passed in plaintext are decrypted from the wallet, so we only ask for the password once
+    let password = if args.source_private_key.is_none() || args.spending_key.is_none() {
+        Some(PasswordSource::from(&config.password).read(false)?)
+    } else {
+        None
+    };
+
     // we now have an sdk, lets derive an address from the source private key and reveal the correspoding public key (if is not already revealed)
 
-    let source_private_key = SecretKey::from_str(&config.source_private_key).unwrap();
+    let source_private_key = keystore::find_secret_key(
+        &sdk,
+        args.source_private_key.as_deref(),
+        &args.source_alias,
+        password.clone(),
+    )
+    .await?;
     let source_public_key = source_private_key.to_public();
     let source_address = Address::from(&source_public_key);
 
@@ -105,13 +261,13 @@ async fn main() {
     .await
     .unwrap_or_default();
 
-    if balance.is_zero() || !balance.can_spend(&Amount::from_u64(config.amount)) {
+    if balance.is_zero() || !balance.can_spend(&Amount::from_u64(args.amount)) {
         tracing::error!(
             "Not enough balance (got {}unam, neeeded {}unam)",
             balance,
-            config.amount
+            args.amount
         );
-        std::process::exit(1);
+        return Err("Not enough balance".to_string());
     } else {
         tracing::info!("Balance is {}unam", balance);
     }
@@ -121,38 +277,28 @@ async fn main() {
     // let source_public_key = wallet.find_public_key("source").unwrap();
     // drop(wallet);
 
-    tracing::info!(
-        "Checkin if {} needs to reveal the public key...",
-        source_public_key
-    );
-
-    // check if public key is already revealed
-    let is_public_key_already_revealed =
-        rpc::is_public_key_revealed(&sdk.namada.clone_client(), &source_address)
-            .await
-            .unwrap_or(false);
-
-    if !is_public_key_already_revealed {
-        tracing::info!("Revealing public key...");
-        execute_reveal_pk(&sdk, source_public_key.clone())
-            .await
-            .unwrap();
-        tracing::info!("Public key revealed!");
-    } else {
-        tracing::info!("Public key already revealed!");
-    }
+    reveal_pk_if_needed(&sdk, source_public_key.clone()).await?;
 
     tracing::info!("Building transfer transaction...");
 
     // we can now make the transfer
-    let target_address = Address::from_str(&config.target_address).unwrap();
+    let target_address = Address::from_str(&args.target_address).unwrap();
 
     let fee_payer = source_public_key.clone();
-    let token_amount = token::Amount::from_u64(config.amount);
+    let token_amount = token::Amount::from_u64(args.amount);
 
     tracing::info!("Executing transparent transfer transaction...");
 
-    execute_transparent_tx(
+    let outgoing = policy::Outgoing {
+        kind: "Transparent",
+        source: source_address.to_string(),
+        target: target_address.to_string(),
+        token: native_token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(&sdk, &outgoing, None).await?;
+
+    let result = execute_transparent_tx(
         &sdk,
         source_address.clone(),
         target_address,
@@ -160,86 +306,86 @@ async fn main() {
         fee_payer.clone(),
         vec![source_public_key.clone()],
         token_amount,
-        config.memo.clone(),
-        config.expiration_timestamp_utc,
+        args.memo.clone(),
+        args.expiration_timestamp_utc,
+        None,
     )
-    .await
-    .unwrap();
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
 
     tracing::info!("Transparent shielding transfer executed!");
 
-    let spending_key = ExtendedSpendingKey::from_str(&config.spending_key).unwrap();
+    let spending_key = keystore::find_spending_key(
+        &sdk,
+        args.spending_key.as_deref(),
+        &args.spending_key_alias,
+        password,
+    )
+    .await?;
     let s_key_raw = ExtendedSpendingKeyMasp::from(spending_key);
     let extended_viewing_key = ExtendedFullViewingKey::from(&spending_key.into());
     let pseudo_spending_key_from_spending_key = PseudoExtendedKey::from(s_key_raw);
 
     let viewing_key = extended_viewing_key.fvk.vk;
-    let (div, _g_d) = find_valid_diversifier(&mut OsRng);
-    let masp_payment_addr = viewing_key
-        .to_payment_address(div)
-        .expect("a PaymentAddress");
+    let masp_payment_addr = keystore::gen_payment_address(&viewing_key);
 
     tracing::info!(
         "Executing shielding transaction to payment address {}...",
         masp_payment_addr
     );
 
-    execute_shielding_tx(
+    let outgoing = policy::Outgoing {
+        kind: "Shielding",
+        source: source_address.to_string(),
+        target: masp_payment_addr.to_string(),
+        token: native_token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(&sdk, &outgoing, None).await?;
+
+    let result = execute_shielding_tx(
         &sdk,
         source_address.clone(),
-        masp_payment_addr.into(),
+        masp_payment_addr,
         native_token.clone(),
         fee_payer.clone(),
         vec![source_public_key.clone()],
         token_amount,
-        config.memo.clone(),
-        config.expiration_timestamp_utc,
+        args.memo.clone(),
+        args.expiration_timestamp_utc,
+        None,
     )
-    .await
-    .unwrap();
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
 
     tracing::info!("Done shielding!");
 
     tracing::info!("Starting to shieldsync (this might take a while)...");
 
-    let mut shielded_ctx = sdk.namada.shielded_mut().await;
-
-    let masp_client = IndexerMaspClient::new(
-        reqwest::Client::new(),
-        reqUrl::parse("https://masp.campfire.tududes.com/api/v1").unwrap(),
-        true,
-        50,
-    );
-    let task_env = MaspLocalTaskEnv::new(16).unwrap();
-    let shutdown_signal = install_shutdown_signal(true);
-
-    let ss_config = ShieldedSyncConfig::builder()
-        .client(masp_client)
-        .fetched_tracker(DevNullProgressBar)
-        .scanned_tracker(DevNullProgressBar)
-        .applied_tracker(DevNullProgressBar)
-        .shutdown_signal(shutdown_signal)
-        .build();
-
-    shielded_ctx
-        .sync(
-            task_env,
-            ss_config,
-            None,
-            &[],
-            &[DatedKeypair::new(viewing_key, None)],
-        )
-        .await
-        .unwrap();
-
-    shielded_ctx.save().await.unwrap();
-    drop(shielded_ctx);
+    shielded::sync(
+        &sdk,
+        shielded::DEFAULT_MASP_INDEXER_URL,
+        &[viewing_key],
+        install_shutdown_signal(true),
+    )
+    .await?;
 
     tracing::info!("Done shieldsyncing!");
 
     tracing::info!("Executing unshielding transaction to {}...", source_address);
 
-    execute_unshielding_tx(
+    let outgoing = policy::Outgoing {
+        kind: "Unshielding",
+        source: args.spending_key_alias.clone(),
+        target: source_address.to_string(),
+        token: native_token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(&sdk, &outgoing, None).await?;
+
+    let result = execute_unshielding_tx(
         &sdk,
         source_address.clone(),
         pseudo_spending_key_from_spending_key,
@@ -247,86 +393,79 @@ async fn main() {
         fee_payer.clone(),
         vec![source_public_key.clone()],
         token_amount,
-        config.memo.clone(),
-        config.expiration_timestamp_utc,
+        args.memo.clone(),
+        args.expiration_timestamp_utc,
+        None,
     )
-    .await
-    .unwrap();
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
 
     tracing::info!("Transparent shielding transfer executed!");
 
-    let spending_key = ExtendedSpendingKey::from_str(&config.spending_key).unwrap();
     let s_key_raw = ExtendedSpendingKeyMasp::from(spending_key);
     let extended_viewing_key = ExtendedFullViewingKey::from(&spending_key.into());
     let pseudo_spending_key_from_spending_key = PseudoExtendedKey::from(s_key_raw);
 
     let viewing_key = extended_viewing_key.fvk.vk;
-    let (div, _g_d) = find_valid_diversifier(&mut OsRng);
-    let masp_payment_addr = viewing_key
-        .to_payment_address(div)
-        .expect("a PaymentAddress");
+    let masp_payment_addr = keystore::gen_payment_address(&viewing_key);
 
     tracing::info!(
         "Executing shielding transaction to {}...",
         masp_payment_addr
     );
 
-    execute_shielding_tx(
+    let outgoing = policy::Outgoing {
+        kind: "Shielding",
+        source: source_address.to_string(),
+        target: masp_payment_addr.to_string(),
+        token: native_token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(&sdk, &outgoing, None).await?;
+
+    let result = execute_shielding_tx(
         &sdk,
         source_address.clone(),
-        masp_payment_addr.into(),
+        masp_payment_addr,
         native_token.clone(),
         fee_payer.clone(),
         vec![source_public_key.clone()],
         token_amount,
-        config.memo.clone(),
-        config.expiration_timestamp_utc,
+        args.memo.clone(),
+        args.expiration_timestamp_utc,
+        None,
     )
-    .await
-    .unwrap();
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
 
     tracing::info!("Done shielding!");
 
     tracing::info!("Starting to shieldsync (this might take a while)...");
 
-    let mut shielded_ctx = sdk.namada.shielded_mut().await;
-
-    let masp_client = IndexerMaspClient::new(
-        reqwest::Client::new(),
-        reqUrl::parse("https://masp.campfire.tududes.com/api/v1").unwrap(),
-        true,
-        50,
-    );
-    let task_env = MaspLocalTaskEnv::new(16).unwrap();
-    let shutdown_signal = install_shutdown_signal(true);
-
-    let ss_config = ShieldedSyncConfig::builder()
-        .client(masp_client)
-        .fetched_tracker(DevNullProgressBar)
-        .scanned_tracker(DevNullProgressBar)
-        .applied_tracker(DevNullProgressBar)
-        .shutdown_signal(shutdown_signal)
-        .build();
-
-    shielded_ctx
-        .sync(
-            task_env,
-            ss_config,
-            None,
-            &[],
-            &[DatedKeypair::new(viewing_key, None)],
-        )
-        .await
-        .unwrap();
-
-    shielded_ctx.save().await.unwrap();
-    drop(shielded_ctx);
+    shielded::sync(
+        &sdk,
+        shielded::DEFAULT_MASP_INDEXER_URL,
+        &[viewing_key],
+        install_shutdown_signal(true),
+    )
+    .await?;
 
     tracing::info!("Done shieldsyncing!");
 
     tracing::info!("Executing unshielding transaction to {}...", source_address);
 
-    execute_unshielding_tx(
+    let outgoing = policy::Outgoing {
+        kind: "Unshielding",
+        source: args.spending_key_alias.clone(),
+        target: source_address.to_string(),
+        token: native_token.clone(),
+        amount: args.amount,
+    };
+    let reservation = policy::check(&sdk, &outgoing, None).await?;
+
+    let result = execute_unshielding_tx(
         &sdk,
         source_address,
         pseudo_spending_key_from_spending_key,
@@ -334,11 +473,15 @@ async fn main() {
         fee_payer,
         vec![source_public_key.clone()],
         token_amount,
-        config.memo.clone(),
-        config.expiration_timestamp_utc,
+        args.memo.clone(),
+        args.expiration_timestamp_utc,
+        None,
     )
-    .await
-    .unwrap();
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
 
     tracing::info!("Done!");
+
+    Ok(())
 }
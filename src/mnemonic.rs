@@ -0,0 +1,139 @@
+use std::fs;
+
+use namada_sdk::{
+    address::Address,
+    key::SchemeType,
+    wallet::{fs::FsWalletUtils, DerivationPath, Language, Mnemonic, MnemonicType, Wallet},
+};
+use rand_core::OsRng;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{AppConfig, DeriveKeyArgs, GenMnemonicArgs, KeyScheme},
+    keystore::{self, PasswordSource},
+};
+
+impl From<KeyScheme> for SchemeType {
+    fn from(scheme: KeyScheme) -> Self {
+        match scheme {
+            KeyScheme::Ed25519 => SchemeType::Ed25519,
+            KeyScheme::Secp256k1 => SchemeType::Secp256k1,
+        }
+    }
+}
+
+// the phrase is the only output on stdout so it can be piped to a file
+pub fn gen_mnemonic(args: &GenMnemonicArgs) -> Result<(), String> {
+    let mnemonic_type = MnemonicType::for_word_count(args.words).map_err(|e| e.to_string())?;
+    let mnemonic = Wallet::<FsWalletUtils>::gen_mnemonic_code(mnemonic_type, &mut OsRng)
+        .map_err(|e| e.to_string())?;
+
+    tracing::warn!("Write the mnemonic down and keep it safe, it can recover every derived key");
+    println!("{}", mnemonic.phrase());
+
+    Ok(())
+}
+
+// derives a key at the given path and stores it encrypted, derivation is deterministic so the same
+// mnemonic and path always give back the same account
+pub fn derive_key(config: &AppConfig, args: &DeriveKeyArgs) -> Result<(), String> {
+    let base_dir = config.base_dir();
+    let mut wallet = keystore::load_wallet(&base_dir);
+
+    let mnemonic = read_mnemonic(args)?;
+    let bip39_passphrase = if args.bip39_passphrase {
+        Zeroizing::new(rpassword::prompt_password("BIP39 passphrase: ").map_err(|e| e.to_string())?)
+    } else {
+        Zeroizing::new(String::new())
+    };
+    let password = PasswordSource::from(&config.password).read(true)?;
+
+    if args.shielded {
+        let derivation_path = shielded_derivation_path(&args.derivation_path)?;
+        let (alias, _spending_key) = wallet
+            .derive_store_hd_spendind_key_from_mnemonic_code(
+                args.alias.clone(),
+                args.force,
+                derivation_path.clone(),
+                Some((mnemonic, bip39_passphrase)),
+                false,
+                Some(password),
+                args.birthday.map(Into::into),
+            )
+            .ok_or_else(|| format!("Unable to derive spending key {}", args.alias))?;
+        let viewing_key = wallet.find_viewing_key(&alias).map_err(|e| e.to_string())?;
+        tracing::info!(
+            "Derived spending key {} at {} (viewing key {})",
+            alias,
+            derivation_path,
+            viewing_key
+        );
+    } else {
+        let scheme = SchemeType::from(args.scheme);
+        let derivation_path = transparent_derivation_path(scheme, &args.derivation_path)?;
+        let (alias, sk) = wallet
+            .derive_store_key_from_mnemonic_code(
+                scheme,
+                args.alias.clone(),
+                args.force,
+                derivation_path.clone(),
+                Some((mnemonic, bip39_passphrase)),
+                false,
+                Some(password),
+            )
+            .ok_or_else(|| format!("Unable to derive secret key {}", args.alias))?;
+        tracing::info!(
+            "Derived secret key {} at {} (address {})",
+            alias,
+            derivation_path,
+            Address::from(&sk.to_public())
+        );
+    }
+
+    wallet.save().map_err(|e| e.to_string())?;
+    tracing::info!("Wallet saved to {}", base_dir.join("wallet.toml").display());
+
+    Ok(())
+}
+
+fn read_mnemonic(args: &DeriveKeyArgs) -> Result<Mnemonic, String> {
+    let phrase = match &args.mnemonic_file {
+        Some(path) => Zeroizing::new(
+            fs::read_to_string(path)
+                .map_err(|e| format!("Can't read mnemonic file {}: {}", path.display(), e))?,
+        ),
+        None => {
+            Zeroizing::new(rpassword::prompt_password("Mnemonic: ").map_err(|e| e.to_string())?)
+        }
+    };
+    Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| format!("Invalid mnemonic: {}", e))
+}
+
+fn transparent_derivation_path(scheme: SchemeType, path: &str) -> Result<DerivationPath, String> {
+    if path.eq_ignore_ascii_case("default") {
+        return Ok(DerivationPath::default_for_transparent_scheme(scheme));
+    }
+    let derivation_path = DerivationPath::from_path_string(path).map_err(|e| e.to_string())?;
+    if !derivation_path.is_compatible(scheme) {
+        return Err(format!(
+            "Derivation path {} is not hardened as required by {:?}",
+            path, scheme
+        ));
+    }
+    if !derivation_path.is_bip44_conform(true) {
+        tracing::warn!("Derivation path {} is not BIP44 conform", path);
+    }
+    Ok(derivation_path)
+}
+
+fn shielded_derivation_path(path: &str) -> Result<DerivationPath, String> {
+    if path.eq_ignore_ascii_case("default") {
+        return Ok(DerivationPath::default_for_shielded());
+    }
+    let derivation_path = DerivationPath::from_path_string(path).map_err(|e| e.to_string())?;
+    if !derivation_path.is_zip32_conform() {
+        tracing::warn!("Derivation path {} is not ZIP32 conform", path);
+    }
+    Ok(derivation_path)
+}
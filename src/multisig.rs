@@ -0,0 +1,294 @@
+use std::str::FromStr;
+
+use namada_sdk::{
+    address::Address,
+    args::TxBuilder,
+    key::common,
+    rpc,
+    token::{self, Amount},
+    tx::data::GasLimit,
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{
+    config::{AppConfig, InitAccountArgs, MultisigTransferArgs, UpdateAccountArgs},
+    keystore::{self, PasswordSource},
+    pipeline, policy,
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    tokens,
+    transparent_transfer::execute_transparent_tx,
+    utils,
+};
+
+pub async fn execute_init_account_tx(
+    sdk: &Sdk,
+    public_keys: Vec<common::PublicKey>,
+    threshold: u8,
+    gas_payer: common::PublicKey,
+) -> Result<Address, String> {
+    let init_account_tx_builder = sdk
+        .namada
+        .new_init_account(public_keys, Some(threshold))
+        .signing_keys(vec![gas_payer.clone()])
+        .gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT))
+        .wrapper_fee_payer(gas_payer);
+
+    let (mut init_account_tx, signing_data) = init_account_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    pipeline::sign_tx(
+        sdk,
+        &mut init_account_tx,
+        &init_account_tx_builder.tx,
+        signing_data,
+        "Init account",
+    )
+    .await?;
+
+    let tx_response = pipeline::submit_tx(
+        sdk,
+        init_account_tx.clone(),
+        &init_account_tx_builder.tx,
+        "Init account",
+        None,
+    )
+    .await?
+    .ok_or_else(|| "No account was initialized".to_string())?;
+
+    utils::get_initialized_accounts(&init_account_tx, &tx_response)
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No account was initialized".to_string())
+}
+
+pub async fn execute_update_account_tx(
+    sdk: &Sdk,
+    account: Address,
+    public_keys: Vec<common::PublicKey>,
+    threshold: Option<u8>,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+) -> Result<bool, String> {
+    let mut update_account_tx_builder =
+        sdk.namada
+            .new_update_account(account, public_keys, threshold.unwrap_or_default());
+    // keep the current threshold when none is given
+    update_account_tx_builder.threshold = threshold;
+    let update_account_tx_builder = update_account_tx_builder
+        .signing_keys(signers)
+        .gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT))
+        .wrapper_fee_payer(gas_payer);
+
+    let (mut update_account_tx, signing_data) = update_account_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    pipeline::sign_tx(
+        sdk,
+        &mut update_account_tx,
+        &update_account_tx_builder.tx,
+        signing_data,
+        "Update account",
+    )
+    .await?;
+
+    pipeline::submit_tx(
+        sdk,
+        update_account_tx,
+        &update_account_tx_builder.tx,
+        "Update account",
+        None,
+    )
+    .await
+    .map(|_| true)
+}
+
+// the account keys are read from chain, we refuse to build a tx that could never reach the threshold
+pub async fn check_threshold(
+    sdk: &Sdk,
+    account: &Address,
+    signers: &[common::PublicKey],
+) -> Result<(), String> {
+    let account_info = rpc::get_account_info(&sdk.namada.clone_client(), account)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} is not an established account", account))?;
+
+    let valid_signers = signers
+        .iter()
+        .filter(|public_key| {
+            account_info
+                .public_keys_map
+                .get_index_from_public_key(public_key)
+                .is_some()
+        })
+        .count();
+
+    tracing::info!(
+        "{} has {} of the {} required signatures",
+        account,
+        valid_signers,
+        account_info.threshold
+    );
+
+    if valid_signers < account_info.threshold as usize {
+        return Err(format!(
+            "Not enough signers for {} (got {}, needed {})",
+            account, valid_signers, account_info.threshold
+        ));
+    }
+    Ok(())
+}
+
+pub async fn init_account(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &InitAccountArgs,
+) -> Result<(), String> {
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let gas_payer = keystore::find_secret_key(sdk, None, &args.gas_payer, Some(password))
+        .await?
+        .to_public();
+
+    let mut public_keys = Vec::with_capacity(args.public_keys.len());
+    for key in &args.public_keys {
+        public_keys.push(keystore::find_public_key(sdk, key).await?);
+    }
+    if args.threshold == 0 || args.threshold as usize > public_keys.len() {
+        return Err(format!(
+            "Threshold must be between 1 and {}",
+            public_keys.len()
+        ));
+    }
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!(
+        "Creating a {}-of-{} account...",
+        args.threshold,
+        public_keys.len()
+    );
+    let address = execute_init_account_tx(sdk, public_keys, args.threshold, gas_payer).await?;
+    tracing::info!("Account {} created!", address);
+
+    let mut wallet = sdk.namada.wallet.write().await;
+    wallet
+        .insert_address(&args.alias, address, args.force)
+        .ok_or_else(|| format!("Alias {} is already used in the wallet", args.alias))?;
+    wallet.save().map_err(|e| e.to_string())?;
+    tracing::info!("Account stored in the wallet as {}", args.alias);
+
+    Ok(())
+}
+
+pub async fn update_account(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &UpdateAccountArgs,
+) -> Result<(), String> {
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let gas_payer = keystore::find_secret_key(sdk, None, &args.gas_payer, Some(password.clone()))
+        .await?
+        .to_public();
+    let signers = keystore::find_signers(sdk, &args.signers, Some(password)).await?;
+    let account = keystore::find_address(sdk, &args.account).await?;
+
+    let mut public_keys = Vec::with_capacity(args.public_keys.len());
+    for key in &args.public_keys {
+        public_keys.push(keystore::find_public_key(sdk, key).await?);
+    }
+
+    check_threshold(sdk, &account, &signers).await?;
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!("Updating account {}...", account);
+    execute_update_account_tx(
+        sdk,
+        account.clone(),
+        public_keys,
+        args.threshold,
+        gas_payer,
+        signers,
+    )
+    .await?;
+    tracing::info!("Account {} updated!", account);
+
+    Ok(())
+}
+
+// the tx is built once and signed by every signer found in the wallet before being submitted
+pub async fn multisig_transfer(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &MultisigTransferArgs,
+) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let gas_payer = keystore::find_secret_key(sdk, None, &args.gas_payer, Some(password.clone()))
+        .await?
+        .to_public();
+    let signers = keystore::find_signers(sdk, &args.signers, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.account).await?;
+    let target_address = Address::from_str(&args.target_address).map_err(|e| e.to_string())?;
+
+    let token_address = tokens::resolve_token(sdk, &config.base_dir(), &args.token).await?;
+    let token_name = tokens::token_display(sdk, &config.base_dir(), &token_address).await;
+
+    tracing::info!("Check {} {} balance...", source_address, token_name);
+    let balance = rpc::get_token_balance(
+        &sdk.namada.clone_client(),
+        &token_address,
+        &source_address,
+        None,
+    )
+    .await
+    .unwrap_or_default();
+
+    if !balance.can_spend(&Amount::from_u64(args.amount)) {
+        return Err(format!(
+            "Not enough {} balance (got {}, neeeded {})",
+            token_name, balance, args.amount
+        ));
+    }
+
+    let outgoing = policy::Outgoing {
+        kind: "Transparent",
+        source: source_address.to_string(),
+        target: target_address.to_string(),
+        token: token_address.clone(),
+        amount: args.amount,
+    };
+    check_threshold(sdk, &source_address, &signers).await?;
+    let reservation = policy::check(sdk, &outgoing, args.request_id.as_deref()).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        tracing::info!("Executing multisig transparent transfer transaction...");
+        execute_transparent_tx(
+            sdk,
+            source_address,
+            target_address,
+            token_address,
+            gas_payer,
+            signers,
+            token::Amount::from_u64(args.amount),
+            args.memo.clone(),
+            args.expiration_timestamp_utc,
+            args.request_id.as_deref(),
+        )
+        .await
+    }
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
+    tracing::info!("Multisig transparent transfer executed!");
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use namada_sdk::{address::Address, rpc};
+use tendermint_rpc::{error::ErrorDetail, Client, Error, HttpClient, SimpleRequest, Url};
+
+use crate::config::NodeArgs;
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+struct Endpoint {
+    url: String,
+    client: HttpClient,
+}
+
+// what the health check learnt about a node
+pub struct NodeStatus {
+    pub chain_id: String,
+    pub height: u64,
+}
+
+// checks that the node answers, isn't catching up and is on the expected chain if any
+pub async fn check_health(
+    client: &HttpClient,
+    chain_id: Option<&str>,
+) -> Result<NodeStatus, String> {
+    let status = client.status().await.map_err(|e| e.to_string())?;
+    let node_chain_id = status.node_info.network.to_string();
+    if let Some(chain_id) = chain_id {
+        if node_chain_id != chain_id {
+            return Err(format!(
+                "node is on chain {}, expected {}",
+                node_chain_id, chain_id
+            ));
+        }
+    }
+    if status.sync_info.catching_up {
+        return Err(format!(
+            "node is catching up (height {})",
+            status.sync_info.latest_block_height
+        ));
+    }
+    Ok(NodeStatus {
+        chain_id: node_chain_id,
+        height: status.sync_info.latest_block_height.value(),
+    })
+}
+
+// a tendermint client over several endpoints: requests go to the current endpoint and move to the
+// next one on transport errors, errors returned by the node itself are not retried
+#[derive(Clone)]
+pub struct FailoverClient {
+    chain_id: String,
+    endpoints: Arc<Vec<Endpoint>>,
+    current: Arc<AtomicUsize>,
+    max_attempts: u32,
+}
+
+impl FailoverClient {
+    // the healthy endpoints are kept, the highest first, the unhealthy ones are logged and dropped
+    pub async fn connect(node: &NodeArgs) -> Result<Self, String> {
+        let mut chain_id = node.chain_id.clone();
+        let mut endpoints = vec![];
+        for url in &node.rpc {
+            let client = Url::from_str(url)
+                .and_then(|url| HttpClient::new(url))
+                .map_err(|e| format!("Invalid RPC address {}: {}", url, e))?;
+            match check_health(&client, chain_id.as_deref()).await {
+                Ok(status) => {
+                    // without `--chain-id` the first healthy node decides it
+                    if chain_id.is_none() {
+                        tracing::info!("Detected chain id {} from RPC {}", status.chain_id, url);
+                        chain_id = Some(status.chain_id.clone());
+                    }
+                    tracing::info!("RPC {} is healthy at height {}", url, status.height);
+                    endpoints.push((
+                        status.height,
+                        Endpoint {
+                            url: url.clone(),
+                            client,
+                        },
+                    ));
+                }
+                Err(e) => tracing::warn!("Skipping RPC {}: {}", url, e),
+            }
+        }
+        let Some(chain_id) = chain_id.filter(|_| !endpoints.is_empty()) else {
+            return Err("No healthy RPC endpoint".to_string());
+        };
+        endpoints.sort_by(|(height_a, _), (height_b, _)| height_b.cmp(height_a));
+
+        Ok(Self {
+            chain_id,
+            endpoints: Arc::new(
+                endpoints
+                    .into_iter()
+                    .map(|(_, endpoint)| endpoint)
+                    .collect(),
+            ),
+            current: Arc::new(AtomicUsize::new(0)),
+            max_attempts: node.rpc_max_attempts,
+        })
+    }
+
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    // refuses a node whose native token isn't the expected one, like a testnet node configured
+    // with a mainnet chain id
+    pub async fn check_native_token(&self, expected: &str) -> Result<(), String> {
+        let expected = Address::from_str(expected)
+            .map_err(|e| format!("Invalid native token {}: {}", expected, e))?;
+        let native_token = rpc::query_native_token(self)
+            .await
+            .map_err(|e| e.to_string())?;
+        if native_token != expected {
+            return Err(format!(
+                "The native token of chain {} is {}, expected {}",
+                self.chain_id, native_token, expected
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn current_url(&self) -> &str {
+        &self.endpoints[self.current.load(Ordering::Relaxed) % self.endpoints.len()].url
+    }
+
+    fn fail_over(&self, from: usize) {
+        let next = (from + 1) % self.endpoints.len();
+        // another request may have moved on already
+        if self
+            .current
+            .compare_exchange(from, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+            && next != from
+        {
+            tracing::warn!(
+                "Failing over from RPC {} to {}",
+                self.endpoints[from].url,
+                self.endpoints[next].url
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl Client for FailoverClient {
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        // requests aren't `Clone`, a copy is made through their JSON form for every retry
+        let request = serde_json::to_value(&request).expect("requests serialize to JSON");
+        let mut attempt = 1;
+        loop {
+            let idx = self.current.load(Ordering::Relaxed) % self.endpoints.len();
+            let endpoint = &self.endpoints[idx];
+            let copy: R = serde_json::from_value(request.clone()).expect("requests round-trip");
+            let error = match endpoint.client.perform(copy).await {
+                Ok(output) => return Ok(output),
+                Err(e) => e,
+            };
+            if matches!(error.detail(), ErrorDetail::Response(_)) || attempt >= self.max_attempts {
+                return Err(error);
+            }
+
+            tracing::warn!(
+                "RPC {} failed (attempt {}/{}): {}",
+                endpoint.url,
+                attempt,
+                self.max_attempts,
+                error
+            );
+            self.fail_over(idx);
+            // a full round over the endpoints failed, give them some time
+            if attempt as usize % self.endpoints.len() == 0 {
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            attempt += 1;
+        }
+    }
+}
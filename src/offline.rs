@@ -0,0 +1,373 @@
+use std::{fs, path::Path, str::FromStr};
+
+use namada_sdk::{
+    account::AccountPublicKeysMap,
+    address::Address,
+    key::common,
+    masp_primitives::zip32::{ExtendedSpendingKey as ExtendedSpendingKeyMasp, PseudoExtendedKey},
+    signing::SigningTxData,
+    token,
+    tx::Tx,
+    Namada, PaymentAddress,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AppConfig, BuildArgs, BuildKind, SignArgs, SubmitArgs},
+    journal,
+    keystore::{self, PasswordSource},
+    pipeline, policy,
+    reveal_pk::build_reveal_pk_tx,
+    sdk::Sdk,
+    shielding_transfer::build_shielding_tx,
+    tokens,
+    transparent_transfer::build_transparent_tx,
+    unshielding_transfer::build_unshielding_tx,
+};
+
+// the part of `SigningTxData` a signer needs without access to a node, the account keys are kept
+// in index order so the map can be rebuilt
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OfflineSigningData {
+    pub owner: Option<Address>,
+    pub public_keys: Vec<common::PublicKey>,
+    pub threshold: u8,
+    pub account_public_keys: Option<Vec<common::PublicKey>>,
+    pub fee_payer: common::PublicKey,
+}
+
+impl From<SigningTxData> for OfflineSigningData {
+    fn from(signing_data: SigningTxData) -> Self {
+        let account_public_keys = signing_data.account_public_keys_map.map(|map| {
+            (0..=u8::MAX)
+                .map_while(|idx| map.get_public_key_from_index(idx))
+                .collect()
+        });
+        Self {
+            owner: signing_data.owner,
+            public_keys: signing_data.public_keys,
+            threshold: signing_data.threshold,
+            account_public_keys,
+            fee_payer: signing_data.fee_payer,
+        }
+    }
+}
+
+// the file moved between the online and the offline machine
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OfflineTx {
+    pub kind: String,
+    pub tx: Tx,
+    pub signing_data: OfflineSigningData,
+    pub signed_by: Vec<common::PublicKey>,
+    pub wrapper_signed: bool,
+}
+
+impl OfflineTx {
+    pub fn new(kind: &str, tx: Tx, signing_data: SigningTxData) -> Self {
+        Self {
+            kind: kind.to_string(),
+            tx,
+            signing_data: signing_data.into(),
+            signed_by: vec![],
+            wrapper_signed: false,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes =
+            fs::read(path).map_err(|e| format!("Can't read tx file {}: {}", path.display(), e))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Invalid tx file {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Can't write tx file {}: {}", path.display(), e))
+    }
+
+    pub fn missing_signatures(&self) -> usize {
+        match self.signing_data.account_public_keys {
+            Some(_) => (self.signing_data.threshold as usize).saturating_sub(self.signed_by.len()),
+            None => 0,
+        }
+    }
+}
+
+pub async fn build(sdk: &Sdk, config: &AppConfig, args: &BuildArgs) -> Result<(), String> {
+    let gas_payer = keystore::find_public_key(sdk, &args.gas_payer).await?;
+    let mut signers = Vec::with_capacity(args.signers.len());
+    for signer in &args.signers {
+        signers.push(keystore::find_public_key(sdk, signer).await?);
+    }
+    if signers.is_empty() {
+        signers.push(gas_payer.clone());
+    }
+
+    // the outcome of an offline tx isn't known here, so it counts in the daily limits once built
+    let (kind, reservation, built) = match &args.kind {
+        BuildKind::Transparent {
+            source,
+            target_address,
+            token,
+            amount,
+        } => {
+            let source = keystore::find_address(sdk, source).await?;
+            let target = Address::from_str(target_address).map_err(|e| e.to_string())?;
+            let token_address = tokens::resolve_token(sdk, &config.base_dir(), token).await?;
+            let outgoing = policy::Outgoing {
+                kind: "Transparent",
+                source: source.to_string(),
+                target: target.to_string(),
+                token: token_address.clone(),
+                amount: *amount,
+            };
+            let reservation = policy::check(sdk, &outgoing, None).await?;
+            (
+                "Transparent",
+                Some(reservation),
+                build_transparent_tx(
+                    sdk,
+                    source,
+                    target,
+                    token_address,
+                    gas_payer,
+                    signers,
+                    token::Amount::from_u64(*amount),
+                    args.memo.clone(),
+                    args.expiration_timestamp_utc,
+                )
+                .await,
+            )
+        }
+        BuildKind::Shielding {
+            source,
+            target_address,
+            token,
+            amount,
+        } => {
+            let source = keystore::find_address(sdk, source).await?;
+            let target = PaymentAddress::from_str(target_address).map_err(|e| e.to_string())?;
+            let token_address = tokens::resolve_token(sdk, &config.base_dir(), token).await?;
+            let outgoing = policy::Outgoing {
+                kind: "Shielding",
+                source: source.to_string(),
+                target: target.to_string(),
+                token: token_address.clone(),
+                amount: *amount,
+            };
+            let reservation = policy::check(sdk, &outgoing, None).await?;
+            (
+                "Shielding",
+                Some(reservation),
+                build_shielding_tx(
+                    sdk,
+                    source,
+                    target,
+                    token_address,
+                    gas_payer,
+                    signers,
+                    token::Amount::from_u64(*amount),
+                    args.memo.clone(),
+                    args.expiration_timestamp_utc,
+                )
+                .await,
+            )
+        }
+        BuildKind::Unshielding {
+            spending_key_alias,
+            spending_key,
+            target_address,
+            token,
+            amount,
+        } => {
+            let password = match spending_key {
+                Some(_) => None,
+                None => Some(PasswordSource::from(&config.password).read(false)?),
+            };
+            let spending_key = keystore::find_spending_key(
+                sdk,
+                spending_key.as_deref(),
+                spending_key_alias,
+                password,
+            )
+            .await?;
+            let s_key_raw = ExtendedSpendingKeyMasp::from(spending_key);
+            let target = Address::from_str(target_address).map_err(|e| e.to_string())?;
+            let token_address = tokens::resolve_token(sdk, &config.base_dir(), token).await?;
+            let outgoing = policy::Outgoing {
+                kind: "Unshielding",
+                source: spending_key_alias.clone(),
+                target: target.to_string(),
+                token: token_address.clone(),
+                amount: *amount,
+            };
+            let reservation = policy::check(sdk, &outgoing, None).await?;
+            (
+                "Unshielding",
+                Some(reservation),
+                build_unshielding_tx(
+                    sdk,
+                    target,
+                    PseudoExtendedKey::from(s_key_raw),
+                    token_address,
+                    gas_payer,
+                    signers,
+                    token::Amount::from_u64(*amount),
+                    args.memo.clone(),
+                    args.expiration_timestamp_utc,
+                )
+                .await,
+            )
+        }
+        BuildKind::RevealPk { public_key } => (
+            "Reveal pk",
+            None,
+            build_reveal_pk_tx(
+                sdk,
+                keystore::find_public_key(sdk, public_key).await?,
+                gas_payer,
+            )
+            .await,
+        ),
+    };
+
+    let dumped = built.and_then(|(tx, signing_data, _tx_args)| {
+        let offline_tx = OfflineTx::new(kind, tx, signing_data);
+        offline_tx.save(&args.dump)?;
+        Ok(offline_tx)
+    });
+    // nothing is broadcast here, a tx that failed to build is never sent
+    match (&reservation, &dumped) {
+        (Some(reservation), Ok(_)) => policy::settle(&config.base_dir(), reservation, &dumped),
+        (Some(reservation), Err(_)) => policy::release(&config.base_dir(), reservation),
+        (None, _) => {}
+    }
+    let offline_tx = dumped?;
+    journal::record(
+        &config.base_dir(),
+        &pipeline::wrapper_hash(&offline_tx.tx),
+        kind,
+        journal::BUILT,
+        None,
+        None,
+    );
+    tracing::info!(
+        "{} tx dumped to {}, it needs {} signature(s) and the wrapper signature of {}",
+        kind,
+        args.dump.display(),
+        offline_tx.missing_signatures(),
+        offline_tx.signing_data.fee_payer
+    );
+
+    Ok(())
+}
+
+// raw signatures are added first, the wrapper signature covers them so it must come last
+pub fn sign(config: &AppConfig, args: &SignArgs) -> Result<(), String> {
+    let mut offline_tx = OfflineTx::load(&args.tx_file)?;
+    if offline_tx.wrapper_signed {
+        return Err("The tx already has its wrapper signature".to_string());
+    }
+
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let mut wallet = keystore::load_wallet(&config.base_dir());
+    let mut keys = Vec::with_capacity(args.signers.len());
+    for alias in &args.signers {
+        keys.push(
+            wallet
+                .find_secret_key(alias, Some(password.clone()))
+                .map_err(|e| format!("Can't find secret key {}: {}", alias, e))?,
+        );
+    }
+
+    let signing_data = offline_tx.signing_data.clone();
+    if let Some(account_public_keys) = signing_data.account_public_keys {
+        let account_public_keys_map = AccountPublicKeysMap::from_iter(account_public_keys);
+        for sk in &keys {
+            let public_key = sk.to_public();
+            if !signing_data.public_keys.contains(&public_key)
+                || offline_tx.signed_by.contains(&public_key)
+            {
+                continue;
+            }
+            offline_tx.tx.sign_raw(
+                vec![sk.clone()],
+                account_public_keys_map.clone(),
+                signing_data.owner.clone(),
+            );
+            tracing::info!("Signed with {}", public_key);
+            offline_tx.signed_by.push(public_key);
+        }
+    }
+
+    let missing_signatures = offline_tx.missing_signatures();
+    match keys
+        .iter()
+        .find(|sk| sk.to_public() == signing_data.fee_payer)
+    {
+        Some(sk) if missing_signatures == 0 => {
+            offline_tx.tx.sign_wrapper(sk.clone());
+            offline_tx.wrapper_signed = true;
+            tracing::info!("Wrapper signed with {}", signing_data.fee_payer);
+        }
+        Some(_) => tracing::warn!(
+            "Wrapper not signed yet, {} more signature(s) needed first",
+            missing_signatures
+        ),
+        None => tracing::info!(
+            "{} more signature(s) needed, then the wrapper signature of {}",
+            missing_signatures,
+            signing_data.fee_payer
+        ),
+    }
+
+    let output = args.output.as_ref().unwrap_or(&args.tx_file);
+    offline_tx.save(output)?;
+    tracing::info!("Signed tx written to {}", output.display());
+    if offline_tx.wrapper_signed {
+        journal::record(
+            &config.base_dir(),
+            &pipeline::wrapper_hash(&offline_tx.tx),
+            &offline_tx.kind,
+            journal::SIGNED,
+            None,
+            None,
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn submit(sdk: &Sdk, args: &SubmitArgs) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let offline_tx = OfflineTx::load(&args.tx_file)?;
+    let missing_signatures = offline_tx.missing_signatures();
+    if missing_signatures > 0 {
+        return Err(format!(
+            "The tx needs {} more signature(s)",
+            missing_signatures
+        ));
+    }
+    if !offline_tx.wrapper_signed {
+        return Err(format!(
+            "The tx is missing the wrapper signature of {}",
+            offline_tx.signing_data.fee_payer
+        ));
+    }
+
+    tracing::info!("Submitting {} tx...", offline_tx.kind);
+    pipeline::submit_tx(
+        sdk,
+        offline_tx.tx,
+        &sdk.namada.tx_builder(),
+        &offline_tx.kind,
+        args.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("{} tx executed!", offline_tx.kind);
+
+    Ok(())
+}
@@ -0,0 +1,345 @@
+use namada_sdk::{
+    account::AccountPublicKeysMap,
+    args,
+    bytes::HEXLOWER,
+    error::Error,
+    signing::SigningTxData,
+    tx::{ProcessTxResponse, Section, Signer as TxSigner, Tx},
+    Namada,
+};
+
+use crate::{
+    confirm::{self, FinalStatus},
+    journal, policy,
+    requests::{RequestRecord, RequestStatus, RequestStore},
+    sdk::Sdk,
+    signer::Signer,
+    utils,
+};
+
+// every tx goes through the same steps: a module specific `build_*` function, then `sign_tx` and
+// `submit_tx`, which can also run separately (see `offline`)
+
+// every step is recorded in the journal, the wrapper hash doesn't change when signatures are added
+pub fn wrapper_hash(tx: &Tx) -> String {
+    tx.wrapper_hash()
+        .map(|h| HEXLOWER.encode(&h.0))
+        .unwrap_or_default()
+}
+
+type SignerData<'a> = (&'a dyn Signer, Option<AccountPublicKeysMap>);
+
+// keys held by the process wallet are used directly, the others are delegated to `sdk.signer`
+pub async fn sign_tx(
+    sdk: &Sdk,
+    tx: &mut Tx,
+    args: &args::Tx,
+    signing_data: SigningTxData,
+    label: &str,
+) -> Result<(), String> {
+    let hash = wrapper_hash(tx);
+    journal::record(&sdk.base_dir, &hash, label, journal::BUILT, None, None);
+
+    let requested = signing_data.clone();
+    let account_public_keys = signing_data.account_public_keys_map.clone();
+    let signed = sdk
+        .namada
+        .sign(
+            tx,
+            args,
+            signing_data,
+            |tx, public_key, parts, (signer, account_public_keys): SignerData<'_>| async move {
+                signer
+                    .sign(tx, public_key, parts, account_public_keys.as_ref())
+                    .await
+                    .map_err(Error::Other)
+            },
+            (sdk.signer.as_ref(), account_public_keys),
+        )
+        .await
+        .map_err(|e| format!("unable to sign tx: {}", e))
+        .and_then(|_| check_signatures(tx, &requested));
+
+    match &signed {
+        Ok(()) => journal::record(&sdk.base_dir, &hash, label, journal::SIGNED, None, None),
+        Err(e) => journal::record(&sdk.base_dir, &hash, label, journal::FAILED, Some(e), None),
+    }
+    signed
+}
+
+// the sdk moves on to the next signer when one fails to sign the raw header, so the signatures
+// attached are checked: every requested signer signed, or enough of them to reach the threshold
+// of the account
+fn check_signatures(tx: &Tx, requested: &SigningTxData) -> Result<(), String> {
+    let raw_header_hash = tx.raw_header_hash();
+    let mut signed = vec![];
+    for section in &tx.sections {
+        let Section::Authorization(authorization) = section else {
+            continue;
+        };
+        if !authorization.targets.contains(&raw_header_hash) {
+            continue;
+        }
+        signed.extend(authorization.signatures.keys().filter_map(|index| {
+            match &authorization.signer {
+                TxSigner::PubKeys(public_keys) => public_keys.get(*index as usize).cloned(),
+                TxSigner::Address(_) => requested
+                    .account_public_keys_map
+                    .as_ref()
+                    .and_then(|keys| keys.get_public_key_from_index(*index)),
+            }
+        }));
+    }
+
+    let missing = requested
+        .public_keys
+        .iter()
+        .filter(|public_key| !signed.contains(public_key))
+        .count();
+    if missing == 0 {
+        return Ok(());
+    }
+    if let Some(keys) = &requested.account_public_keys_map {
+        let account_signatures = requested
+            .public_keys
+            .iter()
+            .filter(|public_key| {
+                signed.contains(public_key) && keys.get_index_from_public_key(public_key).is_some()
+            })
+            .count();
+        if account_signatures >= requested.threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(format!(
+        "unable to sign tx: {} of the {} requested signatures are missing",
+        missing,
+        requested.public_keys.len()
+    ))
+}
+
+// the tx is only broadcast by the sdk, inclusion is then tracked by `confirm` which logs the
+// final status: applied, rejected or expired. With a request id the tx is recorded before being
+// broadcast, and a request that was already submitted is resumed instead of sending a new tx.
+// `None` is returned for a request applied by an earlier run, only its record is kept
+pub async fn submit_tx(
+    sdk: &Sdk,
+    tx: Tx,
+    args: &args::Tx,
+    label: &str,
+    request_id: Option<&str>,
+) -> Result<Option<ProcessTxResponse>, String> {
+    let wrapper_hash = wrapper_hash(&tx);
+
+    if let Some(request_id) = request_id {
+        let store = RequestStore::open(&sdk.base_dir)?;
+        if let Some(record) = store.get(request_id)? {
+            return match record.status {
+                RequestStatus::Applied => {
+                    tracing::info!(
+                        "Request {} was already applied: {} tx {}",
+                        request_id,
+                        record.kind,
+                        record.tx_hash
+                    );
+                    Ok(None)
+                }
+                RequestStatus::Submitted => resume(sdk, request_id, record).await.map(Some),
+                status => Err(format!(
+                    "Request {} already ended as {:?}: {}",
+                    request_id,
+                    status,
+                    record.error.unwrap_or_default()
+                )),
+            };
+        }
+        store.insert(
+            request_id,
+            &RequestRecord {
+                kind: label.to_string(),
+                tx_hash: wrapper_hash.clone(),
+                status: RequestStatus::Submitted,
+                error: None,
+                tx: tx.clone(),
+            },
+        )?;
+    }
+
+    let broadcast_args = args::Tx {
+        broadcast_only: true,
+        ..args.clone()
+    };
+    let broadcast_response = sdk.namada.submit(tx.clone(), &broadcast_args).await;
+
+    tracing::info!("{} wrapper tx hash: {}", label, wrapper_hash);
+
+    tracing::debug!("broadcast result: {:?}", broadcast_response);
+
+    match broadcast_response {
+        Ok(ProcessTxResponse::Broadcast(response)) if response.code.is_err() => {
+            let error = format!("tx refused by the mempool: {}", response.log);
+            return finish(
+                sdk,
+                &tx,
+                label,
+                request_id,
+                FinalStatus::Rejected,
+                Err(error),
+            )
+            .map(Some);
+        }
+        Ok(_) => journal::record(
+            &sdk.base_dir,
+            &wrapper_hash,
+            label,
+            journal::SUBMITTED,
+            None,
+            request_id,
+        ),
+        // the tx may have reached the node anyway, a re-run with the request id resumes it
+        Err(e) => {
+            let error = e.to_string();
+            journal::record(
+                &sdk.base_dir,
+                &wrapper_hash,
+                label,
+                journal::FAILED,
+                Some(&error),
+                request_id,
+            );
+            return Err(error);
+        }
+    }
+
+    track_tx(sdk, tx, label, request_id).await.map(Some)
+}
+
+// called by the commands before anything is checked or built: `true` means there is nothing left
+// to do, the request was either already applied or its recorded tx was resumed until applied. A
+// request never submitted is built again, the spends reserved by an earlier failed run are dropped
+pub async fn check_prior(sdk: &Sdk, request_id: Option<&str>) -> Result<bool, String> {
+    let Some(request_id) = request_id else {
+        return Ok(false);
+    };
+    let Some(record) = RequestStore::open(&sdk.base_dir)?.get(request_id)? else {
+        policy::settle_request(&sdk.base_dir, request_id, false);
+        return Ok(false);
+    };
+    match record.status {
+        RequestStatus::Applied => {
+            tracing::info!(
+                "Request {} was already applied: {} tx {}",
+                request_id,
+                record.kind,
+                record.tx_hash
+            );
+            Ok(true)
+        }
+        RequestStatus::Submitted => resume(sdk, request_id, record).await.map(|_| true),
+        status => Err(format!(
+            "Request {} already ended as {:?} ({} tx {}): {}",
+            request_id,
+            status,
+            record.kind,
+            record.tx_hash,
+            record.error.as_deref().unwrap_or("no error recorded")
+        )),
+    }
+}
+
+// the recorded tx is broadcast again, in case it never reached the node, then tracked
+async fn resume(
+    sdk: &Sdk,
+    request_id: &str,
+    record: RequestRecord,
+) -> Result<ProcessTxResponse, String> {
+    tracing::info!(
+        "Request {} resumes {} tx {}",
+        request_id,
+        record.kind,
+        record.tx_hash
+    );
+    confirm::rebroadcast(sdk, &record.tx, &record.tx_hash).await;
+    track_tx(sdk, record.tx, &record.kind, Some(request_id)).await
+}
+
+async fn track_tx(
+    sdk: &Sdk,
+    tx: Tx,
+    label: &str,
+    request_id: Option<&str>,
+) -> Result<ProcessTxResponse, String> {
+    let tx_response: Result<ProcessTxResponse, Error> = match confirm::track(sdk, &tx).await? {
+        Some(tx_response) => Ok(ProcessTxResponse::Applied(tx_response)),
+        None => {
+            let error = "tx expired before being included".to_string();
+            return finish(
+                sdk,
+                &tx,
+                label,
+                request_id,
+                FinalStatus::Expired,
+                Err(error),
+            );
+        }
+    };
+
+    tracing::debug!("tx result: {:?}", tx_response);
+
+    if utils::is_tx_rejected(&tx, &tx_response) {
+        let error = match tx_response {
+            Ok(tx_response) => utils::get_tx_errors(&tx, &tx_response).unwrap_or_default(),
+            Err(e) => e.to_string(),
+        };
+        finish(
+            sdk,
+            &tx,
+            label,
+            request_id,
+            FinalStatus::Rejected,
+            Err(error),
+        )
+    } else {
+        let tx_response = tx_response.map_err(|e| e.to_string());
+        finish(
+            sdk,
+            &tx,
+            label,
+            request_id,
+            FinalStatus::Applied,
+            tx_response,
+        )
+    }
+}
+
+// logs the final status and records it in the journal and for the request if any, the error of a
+// rejected tx comes from `utils::get_tx_errors`
+fn finish(
+    sdk: &Sdk,
+    tx: &Tx,
+    label: &str,
+    request_id: Option<&str>,
+    status: FinalStatus,
+    result: Result<ProcessTxResponse, String>,
+) -> Result<ProcessTxResponse, String> {
+    tracing::info!("{} tx final status: {}", label, status);
+    journal::record(
+        &sdk.base_dir,
+        &wrapper_hash(tx),
+        label,
+        &status.to_string(),
+        result.as_ref().err().map(String::as_str),
+        request_id,
+    );
+    // settled first: a crash in between leaves the request submitted, so it's resumed and settled
+    // again, which does nothing the second time
+    if let Some(request_id) = request_id {
+        policy::settle_request(&sdk.base_dir, request_id, status == FinalStatus::Applied);
+        RequestStore::open(&sdk.base_dir)?.set_status(
+            request_id,
+            status.into(),
+            result.as_ref().err().cloned(),
+        )?;
+    }
+    result
+}
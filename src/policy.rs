@@ -0,0 +1,853 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use namada_sdk::address::Address;
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AppConfig, ApprovalsArgs, ApproveArgs, OutputFormat},
+    requests::RequestStore,
+    sdk::Sdk,
+    tokens,
+};
+
+// an approval is pending until an operator approves or rejects it, an approved one is used by the
+// first transfer matching it to be applied
+pub const PENDING: &str = "pending";
+pub const APPROVED: &str = "approved";
+pub const REJECTED: &str = "rejected";
+pub const USED: &str = "used";
+
+// a spend is reserved when its transfer passes the checks, then sent once applied. Reserved spends
+// count in the daily limits so concurrent transfers can't exceed them together
+const RESERVED: &str = "reserved";
+const SENT: &str = "sent";
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+// amounts are in the smallest unit of the token, like `--amount`
+#[derive(Deserialize, Default, Debug)]
+pub struct TokenLimits {
+    pub max_per_tx: Option<u64>,
+    // over the last 24 hours
+    pub max_daily: Option<u64>,
+    // transfers of at least this amount are parked until approved
+    pub approval_threshold: Option<u64>,
+}
+
+impl TokenLimits {
+    const NONE: TokenLimits = TokenLimits {
+        max_per_tx: None,
+        max_daily: None,
+        approval_threshold: None,
+    };
+}
+
+// `policy.json` under the base dir, nothing is restricted without it. Tokens are keyed by address,
+// alias or denom trace like `--token`
+#[derive(Deserialize, Default, Debug)]
+pub struct Policy {
+    #[serde(default)]
+    pub tokens: BTreeMap<String, TokenLimits>,
+    // when not empty only these targets can receive transfers
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    #[serde(default)]
+    pub denylist: Vec<String>,
+}
+
+impl Policy {
+    pub fn load(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("policy.json");
+        if !path.exists() {
+            return Ok(Policy::default());
+        }
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Can't read policy {}: {}", path.display(), e))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Invalid policy {}: {}", path.display(), e))
+    }
+
+    fn check_target(&self, target: &str) -> Result<(), String> {
+        if self.denylist.iter().any(|denied| denied == target) {
+            return Err(format!("Target {} is denylisted", target));
+        }
+        if !self.allowlist.is_empty() && !self.allowlist.iter().any(|allowed| allowed == target) {
+            return Err(format!("Target {} is not allowlisted", target));
+        }
+        Ok(())
+    }
+
+    async fn limits(&self, sdk: &Sdk, token: &Address) -> Result<Option<&TokenLimits>, String> {
+        for (key, limits) in &self.tokens {
+            if &tokens::resolve_token(sdk, &sdk.base_dir, key).await? == token {
+                return Ok(Some(limits));
+            }
+        }
+        Ok(None)
+    }
+}
+
+// what leaves a source, the target is an address, a payment address or the receiver on another
+// chain
+pub struct Outgoing {
+    pub kind: &'static str,
+    pub source: String,
+    pub target: String,
+    pub token: Address,
+    pub amount: u64,
+}
+
+// returned by `check` and passed to `settle` once the transfer returns, the approval is only used
+// up when the transfer is applied. A reservation tied to a request is kept with it in the store so
+// that `pipeline` settles it whenever the request reaches its final status, resumed runs included
+#[derive(Debug)]
+pub struct Reservation {
+    spend_id: i64,
+    approval_id: Option<i64>,
+    request_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Approval {
+    pub id: i64,
+    pub kind: String,
+    pub source: String,
+    pub target: String,
+    pub token: String,
+    pub amount: u64,
+    pub status: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn read_approval(row: &Row<'_>) -> rusqlite::Result<Approval> {
+    Ok(Approval {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        source: row.get(2)?,
+        target: row.get(3)?,
+        token: row.get(4)?,
+        amount: row.get::<_, i64>(5)? as u64,
+        status: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// `policy.sqlite` under the base dir: the amounts sent, for the daily limits, and the approvals
+pub struct PolicyStore {
+    conn: Connection,
+}
+
+impl PolicyStore {
+    pub fn open(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("policy.sqlite");
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Can't open policy store {}: {}", path.display(), e))?;
+        conn.busy_timeout(Duration::from_secs(10))
+            .and_then(|_| Self::init(&conn))
+            .map_err(|e| format!("Can't initialize policy store {}: {}", path.display(), e))?;
+        Ok(Self { conn })
+    }
+
+    fn init(conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS spends (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                target TEXT NOT NULL,
+                token TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS spends_token ON spends(token, at);
+            CREATE TABLE IF NOT EXISTS approvals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                source TEXT NOT NULL,
+                target TEXT NOT NULL,
+                token TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+        )?;
+        // columns added later on, the spends recorded before reservations were all sent
+        for (column, definition) in [
+            ("status", "TEXT NOT NULL DEFAULT 'sent'"),
+            ("approval_id", "INTEGER"),
+            ("request_id", "TEXT"),
+        ] {
+            let exists = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('spends') WHERE name = ?1",
+                params![column],
+                |row| row.get::<_, i64>(0),
+            )?;
+            if exists == 0 {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE spends ADD COLUMN {} {};",
+                    column, definition
+                ))?;
+            }
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS spends_approval ON spends(approval_id, status);
+            CREATE INDEX IF NOT EXISTS spends_request ON spends(request_id, status);",
+        )
+    }
+
+    // the checks and the reservation run in a single immediate transaction, so no other transfer
+    // can be checked in between. A parked transfer is committed before its error is returned
+    pub fn reserve(
+        &mut self,
+        limits: Option<&TokenLimits>,
+        outgoing: &Outgoing,
+        request_id: Option<&str>,
+    ) -> Result<Reservation, String> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| e.to_string())?;
+        let limits = limits.unwrap_or(&TokenLimits::NONE);
+
+        if let Some(max_per_tx) = limits.max_per_tx {
+            if outgoing.amount > max_per_tx {
+                return Err(format!(
+                    "Amount {} of {} is over the per tx limit of {}",
+                    outgoing.amount, outgoing.token, max_per_tx
+                ));
+            }
+        }
+        if let Some(max_daily) = limits.max_daily {
+            let spent = spent_since(&tx, &outgoing.token, now() - DAY_SECS)?;
+            if spent + outgoing.amount > max_daily {
+                return Err(format!(
+                    "Amount {} of {} is over the daily limit of {} ({} sent or being sent in the \
+                    last 24 hours)",
+                    outgoing.amount, outgoing.token, max_daily, spent
+                ));
+            }
+        }
+
+        let approval_id = match limits.approval_threshold {
+            Some(threshold) if outgoing.amount >= threshold => {
+                match find_approval(&tx, outgoing)? {
+                    Some(approval) if approval.status == APPROVED => {
+                        if is_reserved(&tx, approval.id)? {
+                            return Err(format!(
+                                "Transfer is already being sent under approval {}",
+                                approval.id
+                            ));
+                        }
+                        tracing::info!("Transfer approved as {}", approval.id);
+                        Some(approval.id)
+                    }
+                    Some(approval) if approval.status == PENDING => {
+                        return Err(format!("Transfer is waiting for approval {}", approval.id))
+                    }
+                    Some(approval) if approval.status == REJECTED => {
+                        return Err(format!("Transfer was rejected in approval {}", approval.id))
+                    }
+                    _ => {
+                        let id = park(&tx, outgoing)?;
+                        tx.commit().map_err(|e| e.to_string())?;
+                        return Err(format!(
+                            "Transfer of {} {} to {} is parked until approval {} is approved",
+                            outgoing.amount, outgoing.token, outgoing.target, id
+                        ));
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        tx.execute(
+            "INSERT INTO spends (kind, target, token, amount, at, status, approval_id,
+                request_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                outgoing.kind,
+                outgoing.target,
+                outgoing.token.to_string(),
+                outgoing.amount as i64,
+                now(),
+                RESERVED,
+                approval_id,
+                request_id
+            ],
+        )
+        .map_err(|e| format!("Can't reserve spend: {}", e))?;
+        let spend_id = tx.last_insert_rowid();
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(Reservation {
+            spend_id,
+            approval_id,
+            request_id: request_id.map(str::to_string),
+        })
+    }
+
+    // the spend is sent and its approval used up together
+    pub fn confirm(&mut self, reservation: &Reservation) -> Result<(), String> {
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        let reserved = tx
+            .execute(
+                "UPDATE spends SET status = ?1 WHERE id = ?2 AND status = ?3",
+                params![SENT, reservation.spend_id, RESERVED],
+            )
+            .map_err(|e| e.to_string())?;
+        if let (1, Some(approval_id)) = (reserved, reservation.approval_id) {
+            tx.execute(
+                "UPDATE approvals SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![USED, now(), approval_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // every reservation of the request at once, from the final status of its tx
+    pub fn settle_request(&mut self, request_id: &str, applied: bool) -> Result<(), String> {
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        if applied {
+            tx.execute(
+                "UPDATE approvals SET status = ?1, updated_at = ?2
+                WHERE id IN (SELECT approval_id FROM spends WHERE request_id = ?3 AND status = ?4)",
+                params![USED, now(), request_id, RESERVED],
+            )
+            .and_then(|_| {
+                tx.execute(
+                    "UPDATE spends SET status = ?1 WHERE request_id = ?2 AND status = ?3",
+                    params![SENT, request_id, RESERVED],
+                )
+            })
+        } else {
+            tx.execute(
+                "DELETE FROM spends WHERE request_id = ?1 AND status = ?2",
+                params![request_id, RESERVED],
+            )
+        }
+        .map_err(|e| format!("Can't settle the spends of request {}: {}", request_id, e))?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // the approval stays approved so the transfer can be retried
+    pub fn release(&self, reservation: &Reservation) -> Result<(), String> {
+        self.conn
+            .execute(
+                "DELETE FROM spends WHERE id = ?1 AND status = ?2",
+                params![reservation.spend_id, RESERVED],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_approval_status(&self, id: i64, status: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE approvals SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![status, now(), id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn get_approval(&self, id: i64) -> Result<Option<Approval>, String> {
+        self.conn
+            .query_row(
+                "SELECT id, kind, source, target, token, amount, status, created_at, updated_at
+                FROM approvals WHERE id = ?1",
+                params![id],
+                read_approval,
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn approvals(&self, status: Option<&str>) -> Result<Vec<Approval>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, kind, source, target, token, amount, status, created_at, updated_at
+                FROM approvals WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![status], read_approval)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+}
+
+fn spent_since(conn: &Connection, token: &Address, since: i64) -> Result<u64, String> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM spends WHERE token = ?1 AND at >= ?2",
+        params![token.to_string(), since],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|spent| spent as u64)
+    .map_err(|e| e.to_string())
+}
+
+// the most recent approval of the same transfer that isn't used yet
+fn find_approval(conn: &Connection, outgoing: &Outgoing) -> Result<Option<Approval>, String> {
+    conn.query_row(
+        "SELECT id, kind, source, target, token, amount, status, created_at, updated_at
+        FROM approvals
+        WHERE kind = ?1 AND source = ?2 AND target = ?3 AND token = ?4 AND amount = ?5
+            AND status != ?6
+        ORDER BY id DESC LIMIT 1",
+        params![
+            outgoing.kind,
+            outgoing.source,
+            outgoing.target,
+            outgoing.token.to_string(),
+            outgoing.amount as i64,
+            USED
+        ],
+        read_approval,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// whether a transfer not final yet holds the approval. A reservation tied to a request holds it
+// until the request is settled, one left behind by a crashed process without a request id stops
+// holding it after a day like it stops counting in the daily limits
+fn is_reserved(conn: &Connection, approval_id: i64) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM spends WHERE approval_id = ?1 AND status = ?2
+                AND (request_id IS NOT NULL OR at >= ?3)
+        )",
+        params![approval_id, RESERVED, now() - DAY_SECS],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn park(conn: &Connection, outgoing: &Outgoing) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO approvals (kind, source, target, token, amount, status, created_at,
+            updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![
+            outgoing.kind,
+            outgoing.source,
+            outgoing.target,
+            outgoing.token.to_string(),
+            outgoing.amount as i64,
+            PENDING,
+            now()
+        ],
+    )
+    .map_err(|e| format!("Can't park transfer: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+// called before a transfer is built, the amount is reserved in the daily limits until the
+// transfer is settled. A transfer over the approval threshold is parked the first time and goes
+// through once approved, re-running it with the same parameters
+pub async fn check(
+    sdk: &Sdk,
+    outgoing: &Outgoing,
+    request_id: Option<&str>,
+) -> Result<Reservation, String> {
+    let policy = Policy::load(&sdk.base_dir)?;
+    policy.check_target(&outgoing.target)?;
+    let limits = policy.limits(sdk, &outgoing.token).await?;
+    PolicyStore::open(&sdk.base_dir)?.reserve(limits, outgoing, request_id)
+}
+
+// a sweep only moves the excess of the hot account to the cold address set by the operator, and
+// that excess changes from one sweep to the next so it would never match an approval. The approval
+// threshold is skipped, the lists and the other limits still apply
+pub async fn check_sweep(sdk: &Sdk, outgoing: &Outgoing) -> Result<Reservation, String> {
+    let policy = Policy::load(&sdk.base_dir)?;
+    policy.check_target(&outgoing.target)?;
+    let limits = policy
+        .limits(sdk, &outgoing.token)
+        .await?
+        .map(|limits| TokenLimits {
+            approval_threshold: None,
+            ..*limits
+        });
+    PolicyStore::open(&sdk.base_dir)?.reserve(limits.as_ref(), outgoing, None)
+}
+
+// called once the transfer returns. A transfer tied to a request is only released here when its
+// tx was never submitted, otherwise `pipeline` settles it from the final status of the request,
+// and keeps it while that status isn't known. Without a request a failed transfer can't be
+// resumed and its tx may still land, it stays reserved and counts in the daily limits for a day
+pub fn settle<T>(base_dir: &Path, reservation: &Reservation, result: &Result<T, String>) {
+    let applied = match &reservation.request_id {
+        Some(request_id) => {
+            match RequestStore::open(base_dir).and_then(|store| store.get(request_id)) {
+                Ok(None) => Ok(false),
+                Ok(Some(_)) => return,
+                Err(e) => Err(e),
+            }
+        }
+        None if result.is_ok() => Ok(true),
+        None => return,
+    };
+    let settled = applied.and_then(|applied| {
+        let mut store = PolicyStore::open(base_dir)?;
+        if applied {
+            store.confirm(reservation)
+        } else {
+            store.release(reservation)
+        }
+    });
+    if let Err(e) = settled {
+        tracing::error!(
+            "Spend reservation {} not settled: {}",
+            reservation.spend_id,
+            e
+        );
+    }
+}
+
+// for a reservation that's never used, like the early check of a queued withdrawal
+pub fn release(base_dir: &Path, reservation: &Reservation) {
+    if let Err(e) = PolicyStore::open(base_dir).and_then(|store| store.release(reservation)) {
+        tracing::error!(
+            "Spend reservation {} not released: {}",
+            reservation.spend_id,
+            e
+        );
+    }
+}
+
+// called by `pipeline` when a request reaches its final status, and with `applied` false for a
+// request that was never submitted so that the reservations of an earlier failed run are dropped
+pub fn settle_request(base_dir: &Path, request_id: &str, applied: bool) {
+    if let Err(e) =
+        PolicyStore::open(base_dir).and_then(|mut store| store.settle_request(request_id, applied))
+    {
+        tracing::error!("{}", e);
+    }
+}
+
+pub fn approvals(config: &AppConfig, args: &ApprovalsArgs) -> Result<(), String> {
+    let approvals = PolicyStore::open(&config.base_dir())?.approvals(args.status.as_deref())?;
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&approvals).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_approvals_table(&approvals),
+    }
+    Ok(())
+}
+
+pub fn approve(config: &AppConfig, args: &ApproveArgs) -> Result<(), String> {
+    let store = PolicyStore::open(&config.base_dir())?;
+    let approval = store
+        .get_approval(args.id)?
+        .ok_or_else(|| format!("Unknown approval {}", args.id))?;
+    if approval.status != PENDING {
+        return Err(format!(
+            "Approval {} is already {}",
+            approval.id, approval.status
+        ));
+    }
+
+    let status = if args.reject { REJECTED } else { APPROVED };
+    store.set_approval_status(approval.id, status)?;
+    tracing::info!(
+        "{} transfer of {} {} from {} to {} {}",
+        approval.kind,
+        approval.amount,
+        approval.token,
+        approval.source,
+        approval.target,
+        status
+    );
+    Ok(())
+}
+
+fn print_approvals_table(approvals: &[Approval]) {
+    if approvals.is_empty() {
+        println!("No approvals");
+        return;
+    }
+
+    println!(
+        "{:>6} {:<10} {:<12} {:>20} {:<46} {:<46}",
+        "ID", "STATUS", "KIND", "AMOUNT", "TOKEN", "TARGET"
+    );
+    for approval in approvals {
+        println!(
+            "{:>6} {:<10} {:<12} {:>20} {:<46} {:<46}",
+            approval.id,
+            approval.status,
+            approval.kind,
+            approval.amount,
+            approval.token,
+            approval.target
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use namada_sdk::address;
+
+    use super::*;
+
+    // a fresh policy store in its own temp dir, removed on drop
+    struct TempStore {
+        dir: PathBuf,
+        store: PolicyStore,
+    }
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("kukoin-policy-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let store = PolicyStore::open(&dir).unwrap();
+            Self { dir, store }
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    // any address does as the token, only equality matters here
+    fn outgoing(amount: u64) -> Outgoing {
+        Outgoing {
+            kind: "Transparent",
+            source: "source".to_string(),
+            target: "target".to_string(),
+            token: address::MASP,
+            amount,
+        }
+    }
+
+    fn limits(
+        max_per_tx: Option<u64>,
+        max_daily: Option<u64>,
+        approval_threshold: Option<u64>,
+    ) -> TokenLimits {
+        TokenLimits {
+            max_per_tx,
+            max_daily,
+            approval_threshold,
+        }
+    }
+
+    fn parked_id(store: &PolicyStore) -> i64 {
+        store.approvals(Some(PENDING)).unwrap()[0].id
+    }
+
+    #[test]
+    fn per_tx_limit() {
+        let mut temp = TempStore::new("per-tx");
+        let limits = limits(Some(100), None, None);
+        assert!(temp
+            .store
+            .reserve(Some(&limits), &outgoing(100), None)
+            .is_ok());
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(101), None)
+            .unwrap_err();
+        assert!(error.contains("per tx limit"), "{}", error);
+    }
+
+    #[test]
+    fn daily_limit_counts_reserved_and_sent_spends() {
+        let mut temp = TempStore::new("daily");
+        let limits = limits(None, Some(100), None);
+        let sent = temp
+            .store
+            .reserve(Some(&limits), &outgoing(40), None)
+            .unwrap();
+        temp.store.confirm(&sent).unwrap();
+        let reserved = temp
+            .store
+            .reserve(Some(&limits), &outgoing(40), None)
+            .unwrap();
+
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(30), None)
+            .unwrap_err();
+        assert!(error.contains("daily limit"), "{}", error);
+
+        // a released reservation frees its amount
+        temp.store.release(&reserved).unwrap();
+        assert!(temp
+            .store
+            .reserve(Some(&limits), &outgoing(60), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn other_tokens_are_not_counted() {
+        let mut temp = TempStore::new("tokens");
+        let limits = limits(None, Some(100), None);
+        let mut other = outgoing(100);
+        other.token = address::POS;
+        temp.store.reserve(Some(&limits), &other, None).unwrap();
+        assert!(temp
+            .store
+            .reserve(Some(&limits), &outgoing(100), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn denylist_and_allowlist() {
+        let policy = Policy {
+            tokens: BTreeMap::new(),
+            allowlist: vec!["allowed".to_string(), "denied".to_string()],
+            denylist: vec!["denied".to_string()],
+        };
+        assert!(policy.check_target("allowed").is_ok());
+        assert!(policy
+            .check_target("denied")
+            .unwrap_err()
+            .contains("denylisted"));
+        assert!(policy
+            .check_target("other")
+            .unwrap_err()
+            .contains("not allowlisted"));
+
+        let open = Policy::default();
+        assert!(open.check_target("other").is_ok());
+    }
+
+    #[test]
+    fn transfer_over_threshold_is_parked_until_approved() {
+        let mut temp = TempStore::new("pending");
+        let limits = limits(None, None, Some(50));
+        assert!(temp
+            .store
+            .reserve(Some(&limits), &outgoing(49), None)
+            .is_ok());
+
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        assert!(error.contains("parked"), "{}", error);
+        let id = parked_id(&temp.store);
+
+        // parked once, then waiting
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        assert!(error.contains("waiting for approval"), "{}", error);
+        assert_eq!(temp.store.approvals(None).unwrap().len(), 1);
+
+        temp.store.set_approval_status(id, REJECTED).unwrap();
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        assert!(error.contains("rejected"), "{}", error);
+    }
+
+    #[test]
+    fn approval_is_used_once_applied() {
+        let mut temp = TempStore::new("approved");
+        let limits = limits(None, None, Some(50));
+        temp.store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        let id = parked_id(&temp.store);
+        temp.store.set_approval_status(id, APPROVED).unwrap();
+
+        let reservation = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap();
+        assert_eq!(reservation.approval_id, Some(id));
+        // still approved, but held by the transfer in flight
+        let approval = temp.store.get_approval(id).unwrap().unwrap();
+        assert_eq!(approval.status, APPROVED);
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        assert!(error.contains("already being sent"), "{}", error);
+
+        // a failed transfer can be retried under the same approval
+        temp.store.release(&reservation).unwrap();
+        let reservation = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap();
+        temp.store.confirm(&reservation).unwrap();
+        let approval = temp.store.get_approval(id).unwrap().unwrap();
+        assert_eq!(approval.status, USED);
+
+        // a used approval parks the next transfer
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(50), None)
+            .unwrap_err();
+        assert!(error.contains("parked"), "{}", error);
+    }
+
+    #[test]
+    fn reservations_of_a_request_are_settled_together() {
+        let mut temp = TempStore::new("request");
+        let limits = limits(None, Some(100), Some(50));
+        temp.store
+            .reserve(Some(&limits), &outgoing(50), Some("request"))
+            .unwrap_err();
+        let id = parked_id(&temp.store);
+        temp.store.set_approval_status(id, APPROVED).unwrap();
+        temp.store
+            .reserve(Some(&limits), &outgoing(50), Some("request"))
+            .unwrap();
+        temp.store
+            .reserve(Some(&limits), &outgoing(10), Some("request"))
+            .unwrap();
+
+        // a failed request frees its amounts, the approval can be used again
+        temp.store.settle_request("request", false).unwrap();
+        assert_eq!(
+            temp.store.get_approval(id).unwrap().unwrap().status,
+            APPROVED
+        );
+        temp.store
+            .reserve(Some(&limits), &outgoing(50), Some("retry"))
+            .unwrap();
+        temp.store
+            .reserve(Some(&limits), &outgoing(40), Some("other"))
+            .unwrap();
+
+        // an applied one counts as sent and uses up the approval
+        temp.store.settle_request("retry", true).unwrap();
+        assert_eq!(temp.store.get_approval(id).unwrap().unwrap().status, USED);
+        let error = temp
+            .store
+            .reserve(Some(&limits), &outgoing(20), None)
+            .unwrap_err();
+        assert!(error.contains("daily limit"), "{}", error);
+    }
+}
@@ -0,0 +1,273 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use namada_sdk::{
+    address::Address, control_flow::install_shutdown_signal, masp_primitives::sapling::ViewingKey,
+    rpc, token::Amount, Namada,
+};
+use serde::Serialize;
+
+use crate::{
+    config::{OutputFormat, ReconcileArgs},
+    journal::{self, Journal, JournalTx},
+    keystore,
+    sdk::Sdk,
+    shielded, tokens,
+};
+
+// the expected balances in the smallest unit, keyed by holding as passed to `--address` or
+// `--viewing-key` then by token as passed to `--token`
+type ExpectedLedger = BTreeMap<String, BTreeMap<String, u64>>;
+
+enum Holding {
+    Transparent(Address),
+    Shielded(ViewingKey),
+}
+
+impl Holding {
+    fn kind(&self) -> &'static str {
+        match self {
+            Holding::Transparent(_) => "transparent",
+            Holding::Shielded(_) => "shielded",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HoldingReport {
+    pub holding: String,
+    pub kind: &'static str,
+    pub token: String,
+    pub balance: String,
+    pub expected: Option<String>,
+    pub difference: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TokenTotal {
+    pub token: String,
+    pub transparent: String,
+    pub shielded: String,
+    pub total: String,
+}
+
+#[derive(Serialize)]
+pub struct ReconcileReport {
+    pub holdings: Vec<HoldingReport>,
+    pub totals: Vec<TokenTotal>,
+    pub in_flight: Vec<JournalTx>,
+    pub discrepancies: Vec<String>,
+}
+
+fn load_expected(path: &Path) -> Result<ExpectedLedger, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Can't read expected ledger {}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Invalid expected ledger {}: {}", path.display(), e))
+}
+
+fn signed_difference(balance: Amount, expected: Amount) -> String {
+    match balance.checked_sub(expected) {
+        Some(surplus) => format!("+{}", surplus),
+        None => format!("-{}", expected.checked_sub(balance).unwrap_or_default()),
+    }
+}
+
+// only queries, nothing is signed so neither the password nor the fee payer are needed. Errors
+// out after printing the report when a discrepancy is found so it can be used in scripts
+pub async fn reconcile(sdk: &Sdk, args: &ReconcileArgs) -> Result<(), String> {
+    if args.addresses.is_empty() && args.viewing_keys.is_empty() {
+        return Err("Nothing to reconcile, pass --address or --viewing-key".to_string());
+    }
+    let expected = match &args.expected {
+        Some(path) => load_expected(path)?,
+        None => ExpectedLedger::new(),
+    };
+
+    let mut holdings = Vec::with_capacity(args.addresses.len() + args.viewing_keys.len());
+    for address in &args.addresses {
+        let holding = Holding::Transparent(keystore::find_address(sdk, address).await?);
+        holdings.push((address.clone(), holding));
+    }
+    let mut viewing_keys = Vec::with_capacity(args.viewing_keys.len());
+    for viewing_key in &args.viewing_keys {
+        let key = keystore::find_viewing_key(sdk, viewing_key).await?;
+        viewing_keys.push(key);
+        holdings.push((viewing_key.clone(), Holding::Shielded(key)));
+    }
+    if args.sync && !viewing_keys.is_empty() {
+        tracing::info!("Syncing the shielded context...");
+        shielded::sync(
+            sdk,
+            &args.masp_indexer_url,
+            &viewing_keys,
+            install_shutdown_signal(true),
+        )
+        .await?;
+    }
+
+    let report = query_reconcile_report(sdk, &holdings, &args.tokens, &expected).await?;
+    let discrepancies = report.discrepancies.len();
+
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_table(&report),
+    }
+
+    if discrepancies > 0 {
+        return Err(format!("{} discrepancies found", discrepancies));
+    }
+    Ok(())
+}
+
+async fn query_reconcile_report(
+    sdk: &Sdk,
+    holdings: &[(String, Holding)],
+    token_keys: &[String],
+    expected: &ExpectedLedger,
+) -> Result<ReconcileReport, String> {
+    let client = sdk.namada.clone_client();
+    let mut discrepancies = vec![];
+    for holding in expected.keys() {
+        if !holdings.iter().any(|(label, _)| label == holding) {
+            discrepancies.push(format!(
+                "{} is in the expected ledger but wasn't reconciled",
+                holding
+            ));
+        }
+    }
+
+    let mut default_tokens = Vec::with_capacity(token_keys.len());
+    for token_key in token_keys {
+        default_tokens.push(tokens::resolve_token(sdk, &sdk.base_dir, token_key).await?);
+    }
+
+    let mut reports = vec![];
+    // (transparent, shielded) per token name
+    let mut totals = BTreeMap::<String, (Amount, Amount)>::new();
+    for (label, holding) in holdings {
+        let mut expected_amounts = BTreeMap::new();
+        for (token_key, amount) in expected.get(label).into_iter().flatten() {
+            let token = tokens::resolve_token(sdk, &sdk.base_dir, token_key).await?;
+            expected_amounts.insert(token, Amount::from_u64(*amount));
+        }
+        let mut holding_tokens = default_tokens.clone();
+        for token in expected_amounts.keys() {
+            if !holding_tokens.contains(token) {
+                holding_tokens.push(token.clone());
+            }
+        }
+
+        for token in holding_tokens {
+            let token_name = tokens::token_display(sdk, &sdk.base_dir, &token).await;
+            let balance = match holding {
+                Holding::Transparent(address) => {
+                    rpc::get_token_balance(&client, &token, address, None)
+                        .await
+                        .map_err(|e| e.to_string())?
+                }
+                Holding::Shielded(viewing_key) => {
+                    shielded::balance(sdk, viewing_key, &token).await?
+                }
+            };
+
+            let total = totals.entry(token_name.clone()).or_default();
+            match holding {
+                Holding::Transparent(_) => total.0 += balance,
+                Holding::Shielded(_) => total.1 += balance,
+            }
+
+            let expected_amount = expected_amounts.get(&token).copied();
+            if let Some(expected_amount) = expected_amount {
+                if balance != expected_amount {
+                    discrepancies.push(format!(
+                        "{} holds {} {} but {} are expected",
+                        label, balance, token_name, expected_amount
+                    ));
+                }
+            }
+            reports.push(HoldingReport {
+                holding: label.clone(),
+                kind: holding.kind(),
+                token: token_name,
+                balance: balance.to_string(),
+                expected: expected_amount.map(|amount| amount.to_string()),
+                difference: expected_amount.map(|amount| signed_difference(balance, amount)),
+            });
+        }
+    }
+
+    // txs signed or broadcast but not final yet can explain a discrepancy, built ones are left
+    // out since offline txs may never be submitted
+    let journal = Journal::open(&sdk.base_dir)?;
+    let mut in_flight = vec![];
+    for status in [journal::SIGNED, journal::SUBMITTED] {
+        in_flight.extend(journal.list(Some(status), None, i64::MAX as usize)?);
+    }
+    in_flight.sort_by_key(|tx| tx.created_at);
+
+    Ok(ReconcileReport {
+        holdings: reports,
+        totals: totals
+            .into_iter()
+            .map(|(token, (transparent, shielded))| TokenTotal {
+                token,
+                transparent: transparent.to_string(),
+                shielded: shielded.to_string(),
+                total: (transparent + shielded).to_string(),
+            })
+            .collect(),
+        in_flight,
+        discrepancies,
+    })
+}
+
+fn print_table(report: &ReconcileReport) {
+    println!(
+        "{:<45} {:<12} {:<12} {:>20} {:>20} {:>20}",
+        "HOLDING", "KIND", "TOKEN", "BALANCE", "EXPECTED", "DIFFERENCE"
+    );
+    for holding in &report.holdings {
+        println!(
+            "{:<45} {:<12} {:<12} {:>20} {:>20} {:>20}",
+            holding.holding,
+            holding.kind,
+            holding.token,
+            holding.balance,
+            holding.expected.as_deref().unwrap_or("-"),
+            holding.difference.as_deref().unwrap_or("-")
+        );
+    }
+
+    println!();
+    println!(
+        "{:<12} {:>20} {:>20} {:>20}",
+        "TOKEN", "TRANSPARENT", "SHIELDED", "TOTAL"
+    );
+    for total in &report.totals {
+        println!(
+            "{:<12} {:>20} {:>20} {:>20}",
+            total.token, total.transparent, total.shielded, total.total
+        );
+    }
+
+    if !report.in_flight.is_empty() {
+        println!();
+        println!("Txs not final yet:");
+        for tx in &report.in_flight {
+            println!("  {} {} ({})", tx.wrapper_hash, tx.kind, tx.status);
+        }
+    }
+
+    println!();
+    if report.discrepancies.is_empty() {
+        println!("No discrepancies");
+        return;
+    }
+    println!("Discrepancies:");
+    for discrepancy in &report.discrepancies {
+        println!("  {}", discrepancy);
+    }
+}
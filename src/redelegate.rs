@@ -0,0 +1,85 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, TxBuilder, TxExpiration},
+    key::common,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    token,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{pipeline, sdk::Sdk};
+
+pub async fn build_redelegate_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    src_validator: Address,
+    dest_validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut redelegate_tx_builder =
+        sdk.namada
+            .new_redelegation(source_address, src_validator, dest_validator, amount);
+    redelegate_tx_builder = redelegate_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    redelegate_tx_builder = redelegate_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        redelegate_tx_builder = redelegate_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        redelegate_tx_builder = redelegate_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    redelegate_tx_builder = redelegate_tx_builder.signing_keys(signers);
+
+    let (redelegate_tx, signing_data) = redelegate_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((redelegate_tx, signing_data, redelegate_tx_builder.tx))
+}
+
+pub async fn execute_redelegate_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    src_validator: Address,
+    dest_validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut redelegate_tx, signing_data, tx_args) = build_redelegate_tx(
+        sdk,
+        source_address,
+        src_validator,
+        dest_validator,
+        gas_payer,
+        signers,
+        amount,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(
+        sdk,
+        &mut redelegate_tx,
+        &tx_args,
+        signing_data,
+        "Redelegate",
+    )
+    .await?;
+
+    pipeline::submit_tx(sdk, redelegate_tx, &tx_args, "Redelegate", request_id)
+        .await
+        .map(|_| true)
+}
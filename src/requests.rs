@@ -0,0 +1,238 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use namada_sdk::tx::Tx;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::confirm::FinalStatus;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestStatus {
+    Submitted,
+    Applied,
+    Rejected,
+    Expired,
+}
+
+impl RequestStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RequestStatus::Submitted => "submitted",
+            RequestStatus::Applied => "applied",
+            RequestStatus::Rejected => "rejected",
+            RequestStatus::Expired => "expired",
+        }
+    }
+}
+
+impl FromStr for RequestStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "submitted" => Ok(RequestStatus::Submitted),
+            "applied" => Ok(RequestStatus::Applied),
+            "rejected" => Ok(RequestStatus::Rejected),
+            "expired" => Ok(RequestStatus::Expired),
+            _ => Err(format!("Unknown request status {}", status)),
+        }
+    }
+}
+
+impl From<FinalStatus> for RequestStatus {
+    fn from(status: FinalStatus) -> Self {
+        match status {
+            FinalStatus::Applied => RequestStatus::Applied,
+            FinalStatus::Rejected => RequestStatus::Rejected,
+            FinalStatus::Expired => RequestStatus::Expired,
+        }
+    }
+}
+
+// the signed tx is kept so that tracking can resume after a crash
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RequestRecord {
+    pub kind: String,
+    pub tx_hash: String,
+    pub status: RequestStatus,
+    pub error: Option<String>,
+    pub tx: Tx,
+}
+
+// the layout of the `requests.json` store used before `requests.sqlite`
+#[derive(Deserialize)]
+struct LegacyStore {
+    requests: BTreeMap<String, RequestRecord>,
+}
+
+// request id -> tx, kept in `requests.sqlite` under the base dir so that a command re-run with the
+// same `--request-id` never submits a second tx. Every write is a single statement, so concurrent
+// commands and daemon handlers never drop each other's records
+pub struct RequestStore {
+    conn: Connection,
+}
+
+fn read_record(
+    row: &Row<'_>,
+) -> rusqlite::Result<(String, String, String, Option<String>, String)> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl RequestStore {
+    pub fn open(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("requests.sqlite");
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Can't open request store {}: {}", path.display(), e))?;
+        conn.busy_timeout(Duration::from_secs(10))
+            .and_then(|_| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS requests (
+                        request_id TEXT PRIMARY KEY,
+                        kind TEXT NOT NULL,
+                        tx_hash TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        error TEXT,
+                        tx TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        updated_at INTEGER NOT NULL
+                    );",
+                )
+            })
+            .map_err(|e| format!("Can't initialize request store {}: {}", path.display(), e))?;
+        let mut store = Self { conn };
+        store.import_legacy(base_dir)?;
+        Ok(store)
+    }
+
+    // the records of `requests.json` are moved over once, in a single transaction, then the file
+    // is renamed so it's never imported twice
+    fn import_legacy(&mut self, base_dir: &Path) -> Result<(), String> {
+        let path = base_dir.join("requests.json");
+        if !path.exists() {
+            return Ok(());
+        }
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Can't read request store {}: {}", path.display(), e))?;
+        let legacy = serde_json::from_slice::<LegacyStore>(&bytes)
+            .map_err(|e| format!("Invalid request store {}: {}", path.display(), e))?;
+
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        for (request_id, record) in &legacy.requests {
+            tx.execute(
+                "INSERT OR IGNORE INTO requests
+                    (request_id, kind, tx_hash, status, error, tx, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![
+                    request_id,
+                    record.kind,
+                    record.tx_hash,
+                    record.status.as_str(),
+                    record.error,
+                    serde_json::to_string(&record.tx).map_err(|e| e.to_string())?,
+                    now(),
+                ],
+            )
+            .map_err(|e| format!("Can't import request {}: {}", request_id, e))?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        // another process importing at the same time may have renamed it already
+        match fs::rename(&path, path.with_extension("json.imported")) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(format!("Can't rename {}: {}", path.display(), e))
+            }
+            _ => {}
+        }
+        tracing::info!(
+            "Imported {} requests from {}",
+            legacy.requests.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, request_id: &str) -> Result<Option<RequestRecord>, String> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT kind, tx_hash, status, error, tx FROM requests WHERE request_id = ?1",
+                params![request_id],
+                read_record,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        row.map(|(kind, tx_hash, status, error, tx)| {
+            Ok(RequestRecord {
+                kind,
+                tx_hash,
+                status: RequestStatus::from_str(&status)?,
+                error,
+                tx: serde_json::from_str(&tx)
+                    .map_err(|e| format!("Invalid tx recorded for {}: {}", request_id, e))?,
+            })
+        })
+        .transpose()
+    }
+
+    // fails when the request id is already recorded, whoever recorded it first owns the request
+    pub fn insert(&self, request_id: &str, record: &RequestRecord) -> Result<(), String> {
+        let tx = serde_json::to_string(&record.tx).map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "INSERT INTO requests
+                    (request_id, kind, tx_hash, status, error, tx, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![
+                    request_id,
+                    record.kind,
+                    record.tx_hash,
+                    record.status.as_str(),
+                    record.error,
+                    tx,
+                    now(),
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| match e.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) => {
+                    format!("Request {} is already being submitted", request_id)
+                }
+                _ => format!("Can't record request {}: {}", request_id, e),
+            })
+    }
+
+    pub fn set_status(
+        &self,
+        request_id: &str,
+        status: RequestStatus,
+        error: Option<String>,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE requests SET status = ?2, error = ?3, updated_at = ?4 WHERE request_id = ?1",
+                params![request_id, status.as_str(), error, now()],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Can't update request {}: {}", request_id, e))
+    }
+}
@@ -1,48 +1,65 @@
 use namada_sdk::{
-    args::TxBuilder, key::common, signing::default_sign, tx::data::GasLimit, Namada,
-    DEFAULT_GAS_LIMIT,
+    address::Address,
+    args::{self, TxBuilder},
+    key::common,
+    rpc,
+    signing::SigningTxData,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
 };
 
-use crate::{sdk::Sdk, utils};
+use crate::{pipeline, sdk::Sdk};
 
-pub async fn execute_reveal_pk(sdk: &Sdk, public_key: common::PublicKey) -> Result<bool, String> {
+pub async fn build_reveal_pk_tx(
+    sdk: &Sdk,
+    public_key: common::PublicKey,
+    gas_payer: common::PublicKey,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
     let reveal_pk_tx_builder = sdk
         .namada
         .new_reveal_pk(public_key.clone())
-        .signing_keys(vec![public_key.clone()])
+        .signing_keys(vec![public_key])
         .gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT))
-        .wrapper_fee_payer(public_key);
+        .wrapper_fee_payer(gas_payer);
 
-    let (mut reveal_tx, signing_data) = reveal_pk_tx_builder
+    let (reveal_tx, signing_data) = reveal_pk_tx_builder
         .build(&sdk.namada)
         .await
         .map_err(|e| e.to_string())?;
 
-    sdk.namada
-        .sign(
-            &mut reveal_tx,
-            &reveal_pk_tx_builder.tx,
-            signing_data,
-            default_sign,
-            (),
-        )
+    Ok((reveal_tx, signing_data, reveal_pk_tx_builder.tx))
+}
+
+pub async fn execute_reveal_pk(sdk: &Sdk, public_key: common::PublicKey) -> Result<bool, String> {
+    let (mut reveal_tx, signing_data, tx_args) =
+        build_reveal_pk_tx(sdk, public_key.clone(), public_key).await?;
+
+    pipeline::sign_tx(sdk, &mut reveal_tx, &tx_args, signing_data, "Reveal pk").await?;
+
+    pipeline::submit_tx(sdk, reveal_tx, &tx_args, "Reveal pk", None)
         .await
-        .expect("unable to sign tx");
+        .map(|_| true)
+}
 
-    let tx = sdk
-        .namada
-        .submit(reveal_tx.clone(), &reveal_pk_tx_builder.tx)
-        .await;
-
-    if utils::is_tx_rejected(&reveal_tx, &tx) {
-        match tx {
-            Ok(tx) => {
-                let errors = utils::get_tx_errors(&reveal_tx, &tx).unwrap_or_default();
-                Err(errors)
-            }
-            Err(e) => Err(e.to_string()),
-        }
+pub async fn reveal_pk_if_needed(sdk: &Sdk, public_key: common::PublicKey) -> Result<(), String> {
+    tracing::info!(
+        "Checkin if {} needs to reveal the public key...",
+        public_key
+    );
+
+    // check if public key is already revealed
+    let is_public_key_already_revealed =
+        rpc::is_public_key_revealed(&sdk.namada.clone_client(), &Address::from(&public_key))
+            .await
+            .unwrap_or(false);
+
+    if !is_public_key_already_revealed {
+        tracing::info!("Revealing public key...");
+        execute_reveal_pk(sdk, public_key).await?;
+        tracing::info!("Public key revealed!");
     } else {
-        Ok(true)
+        tracing::info!("Public key already revealed!");
     }
+
+    Ok(())
 }
@@ -1,57 +1,55 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
 
 use namada_sdk::io::NamadaIo;
 use namada_sdk::wallet::fs::FsWalletUtils;
 use namada_sdk::wallet::Wallet;
 use namada_sdk::{
-    address::{Address, ImplicitAddress},
     args::TxBuilder,
     chain::ChainId,
     io::NullIo,
-    key::common::SecretKey,
     masp::{fs::FsShieldedUtils, ShieldedContext},
     rpc, NamadaImpl,
 };
-use tendermint_rpc::HttpClient;
 
-use crate::config::AppConfig;
+use crate::{node::FailoverClient, signer::Signer};
 
 // thi structure is a wrapper around a Namada Sdk
 pub struct Sdk {
-    pub namada: NamadaImpl<HttpClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    pub namada: NamadaImpl<FailoverClient, FsWalletUtils, FsShieldedUtils, NullIo>,
+    pub signer: Box<dyn Signer>,
+    // where the wallet, the shielded context and the local stores live
+    pub base_dir: PathBuf,
 }
 
 impl Sdk {
-    // creating an Sdk with and storing in the wallet the source address private key with alias `source` and the native token as `nam`
+    // creating an Sdk and storing in the wallet the native token as `nam`, keys are added by the caller
     pub async fn new(
-        config: &AppConfig,
-        http_client: HttpClient,
+        base_dir: PathBuf,
+        http_client: FailoverClient,
         wallet: Wallet<FsWalletUtils>,
         shielded_ctx: ShieldedContext<FsShieldedUtils>,
         io: NullIo,
+        signer: Box<dyn Signer>,
     ) -> Result<Sdk, String> {
-        let sk = SecretKey::from_str(&config.source_private_key).unwrap();
-        let public_key = sk.to_public();
-        let address = Address::Implicit(ImplicitAddress::from(&public_key));
-
+        let chain_id = ChainId::from_str(http_client.chain_id()).map_err(|e| e.to_string())?;
         let namada = NamadaImpl::new(http_client, wallet, shielded_ctx.into(), io)
             .await
             .map_err(|e| e.to_string())?;
-        let namada = namada.chain_id(ChainId::from_str(&config.chain_id).unwrap());
-
-        let mut namada_wallet = namada.wallet.write().await;
-        namada_wallet
-            .insert_keypair("source".to_string(), true, sk, None, Some(address), None)
-            .unwrap();
+        let namada = namada.chain_id(chain_id);
 
         let native_token = rpc::query_native_token(namada.client())
             .await
             .map_err(|e| e.to_string())?;
+        let mut namada_wallet = namada.wallet.write().await;
         namada_wallet
             .insert_address("nam", native_token, true)
             .unwrap();
         drop(namada_wallet);
 
-        Ok(Self { namada })
+        Ok(Self {
+            namada,
+            signer,
+            base_dir,
+        })
     }
 }
@@ -0,0 +1,782 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use namada_sdk::{
+    address::Address,
+    masp_primitives::zip32::{ExtendedSpendingKey as ExtendedSpendingKeyMasp, PseudoExtendedKey},
+    rpc,
+    token::Amount,
+    Namada, PaymentAddress,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::net::TcpListener;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{AppConfig, ServeArgs, SweepParams, TxSigningArgs},
+    journal::{Journal, TxHistory},
+    keystore::{self, PasswordSource},
+    lanes::{self, Lanes},
+    pipeline, policy,
+    requests::{RequestStatus, RequestStore},
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    shielded::{self, SpendingKeys},
+    shielding_transfer::execute_shielding_tx,
+    sweep, tokens,
+    transparent_transfer::execute_transparent_tx,
+    unshielding_transfer::execute_unshielding_tx,
+    withdrawals::{
+        self, LaneSummary, NewWithdrawal, QueueContext, Withdrawal, WithdrawalKind, WithdrawalQueue,
+    },
+};
+
+// the sdk and its shielded context are loaded once and shared by all the requests, the wallet
+// password is read at startup so the keys can be decrypted without a tty
+struct ServeState {
+    sdk: Arc<Sdk>,
+    password: Zeroizing<String>,
+    api_token: String,
+    masp_indexer_url: String,
+    spending_keys: SpendingKeys,
+    next_request: AtomicU64,
+    lanes: Arc<Lanes>,
+}
+
+impl ServeState {
+    // every tx sent by the daemon is tied to a request id so that its hash and status can be
+    // returned, callers should pass their own to be able to retry safely
+    fn request_id(&self, tx: &TxParams) -> String {
+        tx.request_id.clone().unwrap_or_else(|| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            let counter = self.next_request.fetch_add(1, Ordering::Relaxed);
+            format!("serve-{}-{}", now, counter)
+        })
+    }
+}
+
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(error: String) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, error)
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(error: String) -> Self {
+        ApiError::bad_request(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+// the signing options of the tx commands, with the same defaults
+#[derive(Deserialize)]
+struct TxParams {
+    #[serde(default = "default_gas_payer")]
+    gas_payer: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    memo: Option<String>,
+    expiration_timestamp_utc: Option<i64>,
+    request_id: Option<String>,
+}
+
+fn default_gas_payer() -> String {
+    "source".to_string()
+}
+
+fn default_source() -> String {
+    "source".to_string()
+}
+
+fn default_token() -> String {
+    "nam".to_string()
+}
+
+impl TxParams {
+    fn signing_args(&self, request_id: &str) -> TxSigningArgs {
+        TxSigningArgs {
+            gas_payer: self.gas_payer.clone(),
+            signers: self.signers.clone(),
+            expiration_timestamp_utc: self.expiration_timestamp_utc,
+            memo: self.memo.clone(),
+            request_id: Some(request_id.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TxResult {
+    request_id: String,
+    kind: String,
+    tx_hash: String,
+    status: RequestStatus,
+    error: Option<String>,
+}
+
+// called under the lane lock before anything is checked or built: a submitted request is resumed
+// by the pipeline, so it's never reserved nor sent twice, then any recorded request is answered
+// from the store
+async fn prior_result(state: &ServeState, request_id: &str) -> Result<Option<TxResult>, String> {
+    let resumed = pipeline::check_prior(&state.sdk, Some(request_id)).await;
+    let Some(record) = RequestStore::open(&state.sdk.base_dir)?.get(request_id)? else {
+        return resumed.map(|_| None);
+    };
+    Ok(Some(TxResult {
+        request_id: request_id.to_string(),
+        kind: record.kind,
+        tx_hash: record.tx_hash,
+        status: record.status,
+        error: record.error,
+    }))
+}
+
+// once submitted the outcome is in the request store, rejected and expired txs included, so only
+// the errors raised before submission are returned as such. An applied transfer counts in the
+// daily limits, the reservation of a tx whose outcome isn't known yet is kept
+fn tx_result(
+    state: &ServeState,
+    request_id: &str,
+    result: Result<bool, String>,
+    reservation: &policy::Reservation,
+) -> ApiResult<TxResult> {
+    policy::settle(&state.sdk.base_dir, reservation, &result);
+    let record = RequestStore::open(&state.sdk.base_dir)?.get(request_id)?;
+    match (record, result) {
+        (Some(record), _) => Ok(Json(TxResult {
+            request_id: request_id.to_string(),
+            kind: record.kind,
+            tx_hash: record.tx_hash,
+            status: record.status,
+            error: record.error,
+        })),
+        (None, Err(e)) => Err(e.into()),
+        (None, Ok(_)) => Err(ApiError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Request {} has no recorded tx", request_id),
+        )),
+    }
+}
+
+async fn check_balance(
+    sdk: &Sdk,
+    owner: &Address,
+    token: &Address,
+    amount: u64,
+) -> Result<(), String> {
+    let balance = rpc::get_token_balance(&sdk.namada.clone_client(), token, owner, None)
+        .await
+        .unwrap_or_default();
+    if !balance.can_spend(&Amount::from_u64(amount)) {
+        let token_name = tokens::token_display(sdk, &sdk.base_dir, token).await;
+        return Err(format!(
+            "Not enough {} balance (got {}, neeeded {})",
+            token_name, balance, amount
+        ));
+    }
+    Ok(())
+}
+
+async fn authorize(
+    State(state): State<Arc<ServeState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token_eq(token.as_bytes(), state.api_token.as_bytes()));
+    if !authorized {
+        return Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid api token".to_string(),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+// compares the whole token whatever the first difference, to not leak it through timing
+fn token_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Deserialize)]
+struct BalanceQuery {
+    // address or wallet alias, the shielded balance of `spending_key` is returned otherwise
+    owner: Option<String>,
+    spending_key: Option<String>,
+    #[serde(default = "default_token")]
+    token: String,
+}
+
+async fn balance(
+    State(state): State<Arc<ServeState>>,
+    Query(query): Query<BalanceQuery>,
+) -> ApiResult<serde_json::Value> {
+    let sdk = &state.sdk;
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &query.token).await?;
+
+    let (owner, balance) = match &query.owner {
+        Some(owner) => {
+            let address = keystore::find_address(sdk, owner).await?;
+            let balance =
+                rpc::get_token_balance(&sdk.namada.clone_client(), &token, &address, None)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            (address.to_string(), balance)
+        }
+        None => {
+            let (alias, spending_key) = state.spending_keys.get(query.spending_key.as_deref())?;
+            let balance =
+                shielded::balance(sdk, &shielded::viewing_key(spending_key), &token).await?;
+            (alias.to_string(), balance)
+        }
+    };
+
+    Ok(Json(json!({
+        "owner": owner,
+        "token": query.token,
+        "balance": balance.to_string(),
+    })))
+}
+
+#[derive(Deserialize)]
+struct TransferRequest {
+    #[serde(default = "default_source")]
+    source: String,
+    target: String,
+    #[serde(default = "default_token")]
+    token: String,
+    amount: u64,
+    #[serde(flatten)]
+    tx: TxParams,
+}
+
+async fn transfer(
+    State(state): State<Arc<ServeState>>,
+    Json(body): Json<TransferRequest>,
+) -> ApiResult<TxResult> {
+    let request_id = state.request_id(&body.tx);
+    let sdk = &state.sdk;
+    let source = keystore::find_address(sdk, &body.source).await?;
+    let _lane = state.lanes.lock(&lanes::transparent_lane(&source)).await;
+    if let Some(result) = prior_result(&state, &request_id).await? {
+        return Ok(Json(result));
+    }
+    let (gas_payer, signers) = keystore::find_tx_signers(
+        sdk,
+        &body.tx.signing_args(&request_id),
+        Some(state.password.clone()),
+    )
+    .await?;
+    let target = Address::from_str(&body.target).map_err(|e| e.to_string())?;
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &body.token).await?;
+
+    let outgoing = policy::Outgoing {
+        kind: "Transparent",
+        source: source.to_string(),
+        target: target.to_string(),
+        token: token.clone(),
+        amount: body.amount,
+    };
+    check_balance(sdk, &source, &token, body.amount).await?;
+    let reservation = policy::check(sdk, &outgoing, Some(&request_id)).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        tracing::info!(
+            "Request {}: transparent transfer of {} {} to {}",
+            request_id,
+            body.amount,
+            body.token,
+            target
+        );
+        execute_transparent_tx(
+            sdk,
+            source,
+            target,
+            token,
+            gas_payer,
+            signers,
+            Amount::from_u64(body.amount),
+            body.tx.memo.clone(),
+            body.tx.expiration_timestamp_utc,
+            Some(&request_id),
+        )
+        .await
+    }
+    .await;
+    tx_result(&state, &request_id, result, &reservation)
+}
+
+#[derive(Deserialize)]
+struct ShieldRequest {
+    #[serde(default = "default_source")]
+    source: String,
+    // payment address or wallet alias, a fresh one of `spending_key` is derived otherwise
+    payment_address: Option<String>,
+    spending_key: Option<String>,
+    #[serde(default = "default_token")]
+    token: String,
+    amount: u64,
+    #[serde(flatten)]
+    tx: TxParams,
+}
+
+async fn shield(
+    State(state): State<Arc<ServeState>>,
+    Json(body): Json<ShieldRequest>,
+) -> ApiResult<TxResult> {
+    let request_id = state.request_id(&body.tx);
+    let sdk = &state.sdk;
+    let source = keystore::find_address(sdk, &body.source).await?;
+    let _lane = state.lanes.lock(&lanes::transparent_lane(&source)).await;
+    if let Some(result) = prior_result(&state, &request_id).await? {
+        return Ok(Json(result));
+    }
+    let (gas_payer, signers) = keystore::find_tx_signers(
+        sdk,
+        &body.tx.signing_args(&request_id),
+        Some(state.password.clone()),
+    )
+    .await?;
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &body.token).await?;
+    let payment_address = match &body.payment_address {
+        Some(payment_address) => match PaymentAddress::from_str(payment_address) {
+            Ok(payment_address) => payment_address,
+            Err(_) => sdk
+                .namada
+                .wallet
+                .read()
+                .await
+                .find_payment_addr(payment_address)
+                .cloned()
+                .ok_or_else(|| format!("Unknown payment address or alias {}", payment_address))?,
+        },
+        None => {
+            let (_, spending_key) = state.spending_keys.get(body.spending_key.as_deref())?;
+            keystore::gen_payment_address(&shielded::viewing_key(spending_key))
+        }
+    };
+
+    let outgoing = policy::Outgoing {
+        kind: "Shielding",
+        source: source.to_string(),
+        target: payment_address.to_string(),
+        token: token.clone(),
+        amount: body.amount,
+    };
+    check_balance(sdk, &source, &token, body.amount).await?;
+    let reservation = policy::check(sdk, &outgoing, Some(&request_id)).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        tracing::info!(
+            "Request {}: shielding {} {} to {}",
+            request_id,
+            body.amount,
+            body.token,
+            payment_address
+        );
+        execute_shielding_tx(
+            sdk,
+            source,
+            payment_address,
+            token,
+            gas_payer,
+            signers,
+            Amount::from_u64(body.amount),
+            body.tx.memo.clone(),
+            body.tx.expiration_timestamp_utc,
+            Some(&request_id),
+        )
+        .await
+    }
+    .await;
+    tx_result(&state, &request_id, result, &reservation)
+}
+
+#[derive(Deserialize)]
+struct UnshieldRequest {
+    spending_key: Option<String>,
+    target: String,
+    #[serde(default = "default_token")]
+    token: String,
+    amount: u64,
+    #[serde(flatten)]
+    tx: TxParams,
+}
+
+async fn unshield(
+    State(state): State<Arc<ServeState>>,
+    Json(body): Json<UnshieldRequest>,
+) -> ApiResult<TxResult> {
+    let request_id = state.request_id(&body.tx);
+    let sdk = &state.sdk;
+    let (alias, spending_key) = state.spending_keys.get(body.spending_key.as_deref())?;
+    let _lane = state.lanes.lock(&lanes::shielded_lane(alias)).await;
+    if let Some(result) = prior_result(&state, &request_id).await? {
+        return Ok(Json(result));
+    }
+    let (gas_payer, signers) = keystore::find_tx_signers(
+        sdk,
+        &body.tx.signing_args(&request_id),
+        Some(state.password.clone()),
+    )
+    .await?;
+    let target = keystore::find_address(sdk, &body.target).await?;
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &body.token).await?;
+
+    let outgoing = policy::Outgoing {
+        kind: "Unshielding",
+        source: alias.to_string(),
+        target: target.to_string(),
+        token: token.clone(),
+        amount: body.amount,
+    };
+    let reservation = policy::check(sdk, &outgoing, Some(&request_id)).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        // the background sync keeps the context close to the tip, this only catches up on the last
+        // blocks so that the notes spent since then aren't picked again
+        shielded::sync(
+            sdk,
+            &state.masp_indexer_url,
+            &state.spending_keys.viewing_keys(),
+            shielded::NoShutdown,
+        )
+        .await?;
+
+        tracing::info!(
+            "Request {}: unshielding {} {} to {}",
+            request_id,
+            body.amount,
+            body.token,
+            target
+        );
+        execute_unshielding_tx(
+            sdk,
+            target,
+            PseudoExtendedKey::from(ExtendedSpendingKeyMasp::from(spending_key)),
+            token,
+            gas_payer,
+            signers,
+            Amount::from_u64(body.amount),
+            body.tx.memo.clone(),
+            body.tx.expiration_timestamp_utc,
+            Some(&request_id),
+        )
+        .await
+    }
+    .await;
+    tx_result(&state, &request_id, result, &reservation)
+}
+
+async fn sync(State(state): State<Arc<ServeState>>) -> ApiResult<serde_json::Value> {
+    let viewing_keys = state.spending_keys.viewing_keys();
+    shielded::sync(
+        &state.sdk,
+        &state.masp_indexer_url,
+        &viewing_keys,
+        shielded::NoShutdown,
+    )
+    .await?;
+    Ok(Json(json!({ "synced_keys": viewing_keys.len() })))
+}
+
+#[derive(Deserialize)]
+struct WithdrawalRequest {
+    kind: WithdrawalKind,
+    // address or wallet alias of a transparent withdrawal, spending key alias of an unshielding one
+    source: Option<String>,
+    target: String,
+    #[serde(default = "default_token")]
+    token: String,
+    amount: u64,
+    #[serde(flatten)]
+    tx: TxParams,
+}
+
+// everything is resolved now so that a queued withdrawal can't fail on a typo later on
+async fn queue_withdrawal(
+    State(state): State<Arc<ServeState>>,
+    Json(body): Json<WithdrawalRequest>,
+) -> Result<(StatusCode, Json<Withdrawal>), ApiError> {
+    let sdk = &state.sdk;
+    let source = match body.kind {
+        WithdrawalKind::Transparent => {
+            let source = body.source.clone().unwrap_or_else(default_source);
+            keystore::find_address(sdk, &source).await?.to_string()
+        }
+        WithdrawalKind::Unshielding => state
+            .spending_keys
+            .get(body.source.as_deref())?
+            .0
+            .to_string(),
+    };
+    let target = keystore::find_address(sdk, &body.target).await?;
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &body.token).await?;
+    let request_id = state.request_id(&body.tx);
+    let queue = WithdrawalQueue::open(&sdk.base_dir)?;
+    if let Some(withdrawal) = queue.find(&request_id)? {
+        return Ok((StatusCode::OK, Json(withdrawal)));
+    }
+    keystore::find_tx_signers(
+        sdk,
+        &body.tx.signing_args(&request_id),
+        Some(state.password.clone()),
+    )
+    .await?;
+    // only refuses the withdrawal early, it's checked again and its amount reserved once processed
+    let reservation = policy::check(
+        sdk,
+        &policy::Outgoing {
+            kind: body.kind.label(),
+            source: source.clone(),
+            target: target.to_string(),
+            token: token.clone(),
+            amount: body.amount,
+        },
+        None,
+    )
+    .await?;
+    policy::release(&sdk.base_dir, &reservation);
+
+    let withdrawal = queue.push(&NewWithdrawal {
+        request_id,
+        kind: body.kind,
+        source,
+        target: target.to_string(),
+        token: token.to_string(),
+        amount: body.amount,
+        gas_payer: body.tx.gas_payer.clone(),
+        signers: body.tx.signers.clone(),
+        memo: body.tx.memo.clone(),
+    })?;
+    tracing::info!(
+        "Withdrawal {} queued: {} {} to {}",
+        withdrawal.id,
+        withdrawal.amount,
+        body.token,
+        withdrawal.target
+    );
+    Ok((StatusCode::ACCEPTED, Json(withdrawal)))
+}
+
+#[derive(Deserialize)]
+struct WithdrawalsQuery {
+    status: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+async fn withdrawals(
+    State(state): State<Arc<ServeState>>,
+    Query(query): Query<WithdrawalsQuery>,
+) -> ApiResult<Vec<Withdrawal>> {
+    Ok(Json(
+        WithdrawalQueue::open(&state.sdk.base_dir)?.list(query.status.as_deref(), query.limit)?,
+    ))
+}
+
+async fn withdrawal(
+    State(state): State<Arc<ServeState>>,
+    Path(id): Path<i64>,
+) -> ApiResult<Withdrawal> {
+    WithdrawalQueue::open(&state.sdk.base_dir)?
+        .get(id)?
+        .map(Json)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("Unknown withdrawal {}", id)))
+}
+
+async fn queue_summary(
+    State(state): State<Arc<ServeState>>,
+) -> ApiResult<BTreeMap<String, LaneSummary>> {
+    Ok(Json(WithdrawalQueue::open(&state.sdk.base_dir)?.summary()?))
+}
+
+async fn tx_status(
+    State(state): State<Arc<ServeState>>,
+    Path(tx_hash): Path<String>,
+) -> ApiResult<TxHistory> {
+    Journal::open(&state.sdk.base_dir)?
+        .history(&tx_hash)?
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError(
+                StatusCode::NOT_FOUND,
+                format!("Tx {} is not in the journal", tx_hash),
+            )
+        })
+}
+
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// waits for the next sync time, either a fixed interval or a new block
+async fn wait_next_sync(state: &ServeState, interval: Duration, on_block: bool, height: &mut u64) {
+    if !on_block {
+        tokio::time::sleep(interval).await;
+        return;
+    }
+    let client = state.sdk.namada.clone_client();
+    loop {
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+        match rpc::query_block(&client).await {
+            Ok(Some(block)) if block.height.0 > *height => {
+                *height = block.height.0;
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Can't query the latest block: {}", e),
+        }
+    }
+}
+
+// keeps the shielded context close to the chain tip and saved, the requests sharing the context
+// wait for a running sync to end
+async fn background_sync(state: Arc<ServeState>, interval: Duration, on_block: bool) {
+    let mut height = 0;
+    loop {
+        let started = Instant::now();
+        match shielded::sync(
+            &state.sdk,
+            &state.masp_indexer_url,
+            &state.spending_keys.viewing_keys(),
+            shielded::NoShutdown,
+        )
+        .await
+        {
+            Ok(()) => tracing::info!("Shielded context synced in {:?}", started.elapsed()),
+            Err(e) => tracing::warn!("Background shielded sync failed: {}", e),
+        }
+        wait_next_sync(&state, interval, on_block, &mut height).await;
+    }
+}
+
+// sweeps the hot account in its lane so it never races the transfers and withdrawals of the same
+// source
+async fn sweep_job(state: Arc<ServeState>, params: SweepParams, interval: Duration) {
+    loop {
+        match keystore::find_address(&state.sdk, &params.source).await {
+            Ok(source) => {
+                let _lane = state.lanes.lock(&lanes::transparent_lane(&source)).await;
+                if let Err(e) =
+                    sweep::sweep(&state.sdk, &params, &source, Some(state.password.clone())).await
+                {
+                    tracing::warn!("Sweep failed: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Sweep failed: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+pub async fn serve(sdk: Sdk, config: &AppConfig, args: &ServeArgs) -> Result<(), String> {
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let mut spending_keys = SpendingKeys::default();
+    for alias in &args.spending_key_aliases {
+        let spending_key =
+            keystore::find_spending_key(&sdk, None, alias, Some(password.clone())).await?;
+        spending_keys.insert(alias.clone(), spending_key);
+    }
+
+    let sdk = Arc::new(sdk);
+    let lanes = Arc::new(Lanes::default());
+    let queue = Arc::new(QueueContext {
+        sdk: sdk.clone(),
+        password: password.clone(),
+        masp_indexer_url: args.masp_indexer_url.clone(),
+        spending_keys: spending_keys.clone(),
+        lanes: lanes.clone(),
+    });
+    let state = Arc::new(ServeState {
+        sdk,
+        password,
+        api_token: args.api_token.clone(),
+        masp_indexer_url: args.masp_indexer_url.clone(),
+        spending_keys,
+        next_request: AtomicU64::new(0),
+        lanes,
+    });
+
+    if args.sync_on_block || args.sync_interval > 0 {
+        tokio::spawn(background_sync(
+            state.clone(),
+            Duration::from_secs(args.sync_interval),
+            args.sync_on_block,
+        ));
+    }
+    if args.sweep_interval > 0 {
+        if args.sweep.hot_max.is_some() && args.sweep.cold_address.is_none() {
+            return Err("--cold-address is required with --hot-max".to_string());
+        }
+        tokio::spawn(sweep_job(
+            state.clone(),
+            args.sweep.clone(),
+            Duration::from_secs(args.sweep_interval),
+        ));
+    }
+    tokio::spawn(withdrawals::process_queue(
+        queue,
+        args.withdrawal_batch_size,
+    ));
+
+    let app = Router::new()
+        .route("/balance", get(balance))
+        .route("/transfer", post(transfer))
+        .route("/shield", post(shield))
+        .route("/unshield", post(unshield))
+        .route("/sync", post(sync))
+        .route("/tx/:tx_hash", get(tx_status))
+        .route("/withdrawals", post(queue_withdrawal).get(withdrawals))
+        .route("/withdrawals/:id", get(withdrawal))
+        .route("/queue", get(queue_summary))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
+
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .map_err(|e| format!("Can't listen on {}: {}", args.listen, e))?;
+    tracing::info!("Serving on http://{}", args.listen);
+    axum::serve(listener, app)
+        .await
+        .map_err(|e| format!("Server error: {}", e))
+}
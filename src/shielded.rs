@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use namada_sdk::{
+    address::Address,
+    control_flow::ShutdownSignal,
+    io::DevNullProgressBar,
+    masp::{IndexerMaspClient, MaspLocalTaskEnv, ShieldedSyncConfig},
+    masp_primitives::{
+        sapling::ViewingKey,
+        zip32::{ExtendedFullViewingKey, ExtendedSpendingKey as ExtendedSpendingKeyMasp},
+    },
+    rpc,
+    token::Amount,
+    wallet::DatedKeypair,
+    ExtendedSpendingKey, Namada,
+};
+use reqwest::Url;
+
+use crate::sdk::Sdk;
+
+pub const DEFAULT_MASP_INDEXER_URL: &str = "https://masp.campfire.tududes.com/api/v1";
+
+pub fn viewing_key(spending_key: ExtendedSpendingKey) -> ViewingKey {
+    ExtendedFullViewingKey::from(&ExtendedSpendingKeyMasp::from(spending_key))
+        .fvk
+        .vk
+}
+
+// the spending keys the daemon decrypted at startup, by wallet alias
+#[derive(Clone, Default)]
+pub struct SpendingKeys(BTreeMap<String, ExtendedSpendingKey>);
+
+impl SpendingKeys {
+    pub fn insert(&mut self, alias: String, spending_key: ExtendedSpendingKey) {
+        self.0.insert(alias, spending_key);
+    }
+
+    // the first one when no alias is passed
+    pub fn get(&self, alias: Option<&str>) -> Result<(&str, ExtendedSpendingKey), String> {
+        let found = match alias {
+            Some(alias) => self.0.get_key_value(alias),
+            None => self.0.iter().next(),
+        };
+        found
+            .map(|(alias, spending_key)| (alias.as_str(), *spending_key))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown spending key {}, it must be one of --spending-key-alias",
+                    alias.unwrap_or_default()
+                )
+            })
+    }
+
+    pub fn viewing_keys(&self) -> Vec<ViewingKey> {
+        self.0.values().map(|sk| viewing_key(*sk)).collect()
+    }
+}
+
+// for the daemon, which syncs many times over its life: a listener installed for every sync would
+// never be dropped, and Ctrl-C stops the whole process anyway
+pub struct NoShutdown;
+
+impl ShutdownSignal for NoShutdown {
+    fn received(&mut self) -> bool {
+        false
+    }
+}
+
+// fetches the notes of the viewing keys from the MASP indexer and saves the context, the shielded
+// context stays locked for the whole sync so no shielded tx is built from a half-synced state.
+// One-shot commands pass `install_shutdown_signal(true)` to stop the sync on Ctrl-C
+pub async fn sync(
+    sdk: &Sdk,
+    indexer_url: &str,
+    viewing_keys: &[ViewingKey],
+    shutdown_signal: impl ShutdownSignal,
+) -> Result<(), String> {
+    let masp_client = IndexerMaspClient::new(
+        reqwest::Client::new(),
+        Url::parse(indexer_url)
+            .map_err(|e| format!("Invalid MASP indexer url {}: {}", indexer_url, e))?,
+        true,
+        50,
+    );
+    let task_env = MaspLocalTaskEnv::new(16).map_err(|e| e.to_string())?;
+
+    let ss_config = ShieldedSyncConfig::builder()
+        .client(masp_client)
+        .fetched_tracker(DevNullProgressBar)
+        .scanned_tracker(DevNullProgressBar)
+        .applied_tracker(DevNullProgressBar)
+        .shutdown_signal(shutdown_signal)
+        .build();
+
+    let viewing_keys = viewing_keys
+        .iter()
+        .map(|viewing_key| DatedKeypair::new(*viewing_key, None))
+        .collect::<Vec<_>>();
+
+    let mut shielded_ctx = sdk.namada.shielded_mut().await;
+    shielded_ctx
+        .sync(task_env, ss_config, None, &[], &viewing_keys)
+        .await
+        .map_err(|e| format!("Shielded sync failed: {}", e))?;
+    shielded_ctx.save().await.map_err(|e| e.to_string())
+}
+
+// the balance as of the last sync, with the rewards converted to the current MASP epoch
+pub async fn balance(
+    sdk: &Sdk,
+    viewing_key: &ViewingKey,
+    token: &Address,
+) -> Result<Amount, String> {
+    let client = sdk.namada.clone_client();
+    let masp_epoch = rpc::query_masp_epoch(&client)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut shielded_ctx = sdk.namada.shielded_mut().await;
+    let balance = shielded_ctx
+        .compute_exchanged_balance(&client, sdk.namada.io(), viewing_key, masp_epoch)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "The viewing key was never synced".to_string())?;
+    let (balances, _) = shielded_ctx
+        .decode_combine_sum_to_epoch(&client, balance, masp_epoch)
+        .await;
+
+    Ok(Amount::from_change(balances.get(token)))
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use namada_sdk::{
+    account::AccountPublicKeysMap,
+    key::common,
+    signing::Signable,
+    tx::{Authorization, Section, Tx},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+use crate::config::{SignerArgs, SigningBackend};
+
+// the signing step of the tx pipeline, called for every key that the process wallet doesn't hold.
+// The raw header signature goes at the index of the key in `account_public_keys`
+#[async_trait]
+pub trait Signer: Send + Sync {
+    async fn sign(
+        &self,
+        tx: Tx,
+        public_key: common::PublicKey,
+        parts: HashSet<Signable>,
+        account_public_keys: Option<&AccountPublicKeysMap>,
+    ) -> Result<Tx, String>;
+}
+
+pub fn from_args(args: &SignerArgs) -> Result<Box<dyn Signer>, String> {
+    match args.signing_backend {
+        SigningBackend::Wallet => Ok(Box::new(WalletSigner)),
+        SigningBackend::Remote => {
+            let url = args.remote_signer_url.as_deref().ok_or_else(|| {
+                "--remote-signer-url is required by the remote backend".to_string()
+            })?;
+            Ok(Box::new(RemoteSigner::from_str(url)?))
+        }
+        SigningBackend::File => {
+            let path = args
+                .signing_keys_file
+                .as_deref()
+                .ok_or_else(|| "--signing-keys-file is required by the file backend".to_string())?;
+            Ok(Box::new(FileSigner::load(path)?))
+        }
+    }
+}
+
+// keys are looked up by the sdk in the process wallet before the signer is called, so it's only
+// called for a key the wallet doesn't hold
+pub struct WalletSigner;
+
+#[async_trait]
+impl Signer for WalletSigner {
+    async fn sign(
+        &self,
+        _tx: Tx,
+        public_key: common::PublicKey,
+        _parts: HashSet<Signable>,
+        _account_public_keys: Option<&AccountPublicKeysMap>,
+    ) -> Result<Tx, String> {
+        Err(format!("No key for {}", public_key))
+    }
+}
+
+#[derive(Serialize)]
+struct SignRequest<'a> {
+    tx: &'a Tx,
+    public_key: String,
+    parts: Vec<String>,
+    index: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    tx: Option<Tx>,
+    error: Option<String>,
+}
+
+// talks to a separate process holding the keys, either over HTTP (`POST <url>/sign`) or over a unix
+// socket (`unix:///path`, one JSON request and one JSON response per line)
+pub enum RemoteSigner {
+    Http(reqwest::Client, reqwest::Url),
+    Unix(PathBuf),
+}
+
+impl FromStr for RemoteSigner {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        match url.strip_prefix("unix://") {
+            Some(path) => Ok(RemoteSigner::Unix(path.into())),
+            None => {
+                let url = reqwest::Url::parse(url)
+                    .and_then(|url| url.join("sign"))
+                    .map_err(|e| format!("Invalid remote signer url {}: {}", url, e))?;
+                Ok(RemoteSigner::Http(reqwest::Client::new(), url))
+            }
+        }
+    }
+}
+
+impl RemoteSigner {
+    async fn request(&self, request: &SignRequest<'_>) -> Result<SignResponse, String> {
+        match self {
+            RemoteSigner::Http(client, url) => client
+                .post(url.clone())
+                .json(request)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| format!("Remote signer request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid remote signer response: {}", e)),
+            RemoteSigner::Unix(path) => {
+                let mut stream = UnixStream::connect(path).await.map_err(|e| {
+                    format!("Can't connect to remote signer {}: {}", path.display(), e)
+                })?;
+                let mut line = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+                line.push(b'\n');
+                stream
+                    .write_all(&line)
+                    .await
+                    .map_err(|e| format!("Remote signer request failed: {}", e))?;
+
+                let mut response = String::new();
+                BufReader::new(stream)
+                    .read_line(&mut response)
+                    .await
+                    .map_err(|e| format!("Remote signer request failed: {}", e))?;
+                serde_json::from_str(&response)
+                    .map_err(|e| format!("Invalid remote signer response: {}", e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign(
+        &self,
+        tx: Tx,
+        public_key: common::PublicKey,
+        parts: HashSet<Signable>,
+        account_public_keys: Option<&AccountPublicKeysMap>,
+    ) -> Result<Tx, String> {
+        let request = SignRequest {
+            tx: &tx,
+            public_key: public_key.to_string(),
+            parts: parts.iter().map(|part| format!("{:?}", part)).collect(),
+            index: account_public_keys.and_then(|keys| keys.index_from_pk(&public_key)),
+        };
+        let response = self.request(&request).await?;
+        match (response.tx, response.error) {
+            (_, Some(error)) => Err(format!("Remote signer refused to sign: {}", error)),
+            (Some(signed_tx), None) => Ok(signed_tx),
+            (None, None) => Err("Remote signer returned no tx".to_string()),
+        }
+    }
+}
+
+// plaintext secret keys, one per line, only meant for tests and local networks
+pub struct FileSigner {
+    keys: HashMap<common::PublicKey, common::SecretKey>,
+}
+
+impl FileSigner {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Can't read signing keys file {}: {}", path.display(), e))?;
+        let keys = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                common::SecretKey::from_str(line)
+                    .map(|sk| (sk.to_public(), sk))
+                    .map_err(|e| format!("Invalid secret key in {}: {}", path.display(), e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { keys })
+    }
+}
+
+#[async_trait]
+impl Signer for FileSigner {
+    async fn sign(
+        &self,
+        mut tx: Tx,
+        public_key: common::PublicKey,
+        parts: HashSet<Signable>,
+        account_public_keys: Option<&AccountPublicKeysMap>,
+    ) -> Result<Tx, String> {
+        let Some(sk) = self.keys.get(&public_key) else {
+            return Err(format!("No key for {}", public_key));
+        };
+        if parts.contains(&Signable::RawHeader) {
+            let index = account_public_keys
+                .and_then(|keys| keys.index_from_pk(&public_key))
+                .ok_or_else(|| format!("{} is not a key of the signing account", public_key))?;
+            tx.add_section(Section::Authorization(Authorization::new(
+                vec![tx.raw_header_hash()],
+                BTreeMap::from([(index, sk.clone())]),
+                None,
+            )));
+        }
+        if parts.contains(&Signable::FeeHeader) {
+            tx.sign_wrapper(sk.clone());
+        }
+        Ok(tx)
+    }
+}
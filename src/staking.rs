@@ -0,0 +1,361 @@
+use namada_sdk::{
+    address::Address,
+    rpc,
+    token::{self, Amount},
+    Namada,
+};
+
+use crate::{
+    bond::execute_bond_tx,
+    claim_rewards::execute_claim_rewards_tx,
+    config::{AppConfig, BondArgs, ClaimRewardsArgs, RedelegateArgs, UnbondArgs, WithdrawArgs},
+    keystore::{self, PasswordSource},
+    pipeline,
+    redelegate::execute_redelegate_tx,
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    unbond::execute_unbond_tx,
+    withdraw::execute_withdraw_tx,
+};
+
+pub async fn find_validator(sdk: &Sdk, address_or_alias: &str) -> Result<Address, String> {
+    let validator = keystore::find_address(sdk, address_or_alias).await?;
+    let is_validator = rpc::is_validator(&sdk.namada.clone_client(), &validator)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !is_validator {
+        return Err(format!("{} is not a validator", validator));
+    }
+    Ok(validator)
+}
+
+// logs the pending unbonds of `source` with the epoch from which each can be withdrawn
+async fn log_unbonds(sdk: &Sdk, source: &Address, validator: &Address) -> Result<(), String> {
+    let unbonds = rpc::query_unbond_with_slashing(&sdk.namada.clone_client(), source, validator)
+        .await
+        .map_err(|e| e.to_string())?;
+    for ((start_epoch, withdraw_epoch), amount) in unbonds {
+        tracing::info!(
+            "Unbonded {}unam at epoch {}, withdrawable from epoch {}",
+            amount,
+            start_epoch,
+            withdraw_epoch
+        );
+    }
+    Ok(())
+}
+
+pub async fn bond(sdk: &Sdk, config: &AppConfig, args: &BondArgs) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+    let validator = find_validator(sdk, &args.validator).await?;
+
+    let native_token = rpc::query_native_token(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!("Check {} nam balance...", source_address);
+    let balance = rpc::get_token_balance(
+        &sdk.namada.clone_client(),
+        &native_token,
+        &source_address,
+        None,
+    )
+    .await
+    .unwrap_or_default();
+
+    if !balance.can_spend(&Amount::from_u64(args.amount)) {
+        return Err(format!(
+            "Not enough balance (got {}unam, neeeded {}unam)",
+            balance, args.amount
+        ));
+    }
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!("Bonding {}unam to {}...", args.amount, validator);
+    execute_bond_tx(
+        sdk,
+        source_address,
+        validator,
+        gas_payer,
+        signers,
+        token::Amount::from_u64(args.amount),
+        args.tx.memo.clone(),
+        args.tx.expiration_timestamp_utc,
+        args.tx.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("Bond executed, it becomes active after the pipeline length");
+
+    Ok(())
+}
+
+pub async fn unbond(sdk: &Sdk, config: &AppConfig, args: &UnbondArgs) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+    let validator = find_validator(sdk, &args.validator).await?;
+
+    let epoch = rpc::query_epoch(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+    let bonded = rpc::get_bond_amount_at(
+        &sdk.namada.clone_client(),
+        &source_address,
+        &validator,
+        epoch,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !bonded.can_spend(&Amount::from_u64(args.amount)) {
+        return Err(format!(
+            "Not enough bonded to {} (got {}unam, neeeded {}unam)",
+            validator, bonded, args.amount
+        ));
+    }
+    tracing::info!("Bonded to {} at epoch {}: {}unam", validator, epoch, bonded);
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!("Unbonding {}unam from {}...", args.amount, validator);
+    execute_unbond_tx(
+        sdk,
+        source_address.clone(),
+        validator.clone(),
+        gas_payer,
+        signers,
+        token::Amount::from_u64(args.amount),
+        args.tx.memo.clone(),
+        args.tx.expiration_timestamp_utc,
+        args.tx.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("Unbond executed!");
+
+    log_unbonds(sdk, &source_address, &validator).await
+}
+
+pub async fn withdraw(sdk: &Sdk, config: &AppConfig, args: &WithdrawArgs) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+    let validator = find_validator(sdk, &args.validator).await?;
+
+    let epoch = rpc::query_epoch(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+    let withdrawable = rpc::query_withdrawable_tokens(
+        &sdk.namada.clone_client(),
+        &source_address,
+        &validator,
+        Some(epoch),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if withdrawable.is_zero() {
+        log_unbonds(sdk, &source_address, &validator).await?;
+        return Err(format!(
+            "Nothing to withdraw from {} at epoch {}",
+            validator, epoch
+        ));
+    }
+    tracing::info!("Withdrawable at epoch {}: {}unam", epoch, withdrawable);
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!("Withdrawing from {}...", validator);
+    execute_withdraw_tx(
+        sdk,
+        source_address,
+        validator,
+        gas_payer,
+        signers,
+        args.tx.memo.clone(),
+        args.tx.expiration_timestamp_utc,
+        args.tx.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("Withdrawn {}unam!", withdrawable);
+
+    Ok(())
+}
+
+pub async fn redelegate(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &RedelegateArgs,
+) -> Result<(), String> {
+    if pipeline::check_prior(sdk, args.tx.request_id.as_deref()).await? {
+        return Ok(());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+    let src_validator = find_validator(sdk, &args.src_validator).await?;
+    let dest_validator = find_validator(sdk, &args.dest_validator).await?;
+    if src_validator == dest_validator {
+        return Err("Source and destination validators must differ".to_string());
+    }
+
+    let epoch = rpc::query_epoch(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+    let bonded = rpc::get_bond_amount_at(
+        &sdk.namada.clone_client(),
+        &source_address,
+        &src_validator,
+        epoch,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !bonded.can_spend(&Amount::from_u64(args.amount)) {
+        return Err(format!(
+            "Not enough bonded to {} (got {}unam, neeeded {}unam)",
+            src_validator, bonded, args.amount
+        ));
+    }
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    tracing::info!(
+        "Redelegating {}unam from {} to {}...",
+        args.amount,
+        src_validator,
+        dest_validator
+    );
+    execute_redelegate_tx(
+        sdk,
+        source_address,
+        src_validator,
+        dest_validator,
+        gas_payer,
+        signers,
+        token::Amount::from_u64(args.amount),
+        args.tx.memo.clone(),
+        args.tx.expiration_timestamp_utc,
+        args.tx.request_id.as_deref(),
+    )
+    .await?;
+    tracing::info!("Redelegation executed!");
+
+    Ok(())
+}
+
+// the claimed amounts are estimates: the rewards queried right before each claim, at the current
+// epoch. The applied tx doesn't report the amount it moved
+pub async fn claim_rewards(
+    sdk: &Sdk,
+    config: &AppConfig,
+    args: &ClaimRewardsArgs,
+) -> Result<(), String> {
+    // the claimed amount isn't known anymore when a claim is found applied on a re-run
+    if args.restake && args.tx.request_id.is_some() {
+        return Err("--request-id can't be combined with --restake".to_string());
+    }
+
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let (gas_payer, signers) = keystore::find_tx_signers(sdk, &args.tx, Some(password)).await?;
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+
+    let epoch = rpc::query_epoch(&sdk.namada.clone_client())
+        .await
+        .map_err(|e| e.to_string())?;
+    let validators = match &args.validator {
+        Some(validator) => vec![find_validator(sdk, validator).await?],
+        None => rpc::get_delegation_validators(&sdk.namada.clone_client(), &source_address, epoch)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect(),
+    };
+
+    reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+    let mut total_claimed = Amount::zero();
+    for validator in validators {
+        // one request per validator, each claim is a separate tx
+        let request_id = args
+            .tx
+            .request_id
+            .as_ref()
+            .map(|request_id| format!("{}/{}", request_id, validator));
+        if pipeline::check_prior(sdk, request_id.as_deref()).await? {
+            tracing::info!(
+                "The claim from {} was applied by a previous run, its amount isn't in the total",
+                validator
+            );
+            continue;
+        }
+
+        let rewards = rpc::query_rewards(
+            &sdk.namada.clone_client(),
+            &Some(source_address.clone()),
+            &validator,
+            &Some(epoch),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        if rewards.is_zero() {
+            tracing::info!("No rewards to claim from {}", validator);
+            continue;
+        }
+
+        tracing::info!(
+            "Claiming an estimated {}unam of rewards from {}...",
+            rewards,
+            validator
+        );
+        execute_claim_rewards_tx(
+            sdk,
+            source_address.clone(),
+            validator.clone(),
+            gas_payer.clone(),
+            signers.clone(),
+            args.tx.memo.clone(),
+            args.tx.expiration_timestamp_utc,
+            request_id.as_deref(),
+        )
+        .await?;
+        tracing::info!("Claimed an estimated {}unam from {}", rewards, validator);
+        total_claimed = total_claimed.checked_add(rewards).unwrap_or(total_claimed);
+
+        if args.restake {
+            tracing::info!("Restaking {}unam to {}...", rewards, validator);
+            execute_bond_tx(
+                sdk,
+                source_address.clone(),
+                validator.clone(),
+                gas_payer.clone(),
+                signers.clone(),
+                rewards,
+                args.tx.memo.clone(),
+                args.tx.expiration_timestamp_utc,
+                None,
+            )
+            .await?;
+            tracing::info!("Restaked {}unam to {}", rewards, validator);
+        }
+    }
+
+    tracing::info!(
+        "Claimed an estimated {}unam of rewards in total for {}",
+        total_claimed,
+        source_address
+    );
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use namada_sdk::{address::Address, rpc, Namada};
+use serde::Serialize;
+
+use crate::{
+    config::{OutputFormat, StakingReportArgs},
+    keystore,
+    sdk::Sdk,
+};
+
+#[derive(Serialize)]
+pub struct UnbondReport {
+    pub amount: String,
+    pub start_epoch: u64,
+    pub withdrawable_epoch: u64,
+    pub withdrawable: bool,
+}
+
+#[derive(Serialize)]
+pub struct ValidatorReport {
+    pub validator: String,
+    pub state: String,
+    pub commission_rate: Option<String>,
+    pub bonded: String,
+    pub unbonds: Vec<UnbondReport>,
+    pub pending_rewards: String,
+}
+
+#[derive(Serialize)]
+pub struct StakingReport {
+    pub source: String,
+    pub epoch: u64,
+    pub validators: Vec<ValidatorReport>,
+}
+
+// only queries, nothing is signed so neither the password nor the fee payer are needed
+pub async fn staking_report(sdk: &Sdk, args: &StakingReportArgs) -> Result<(), String> {
+    let source_address = keystore::find_address(sdk, &args.source).await?;
+    let report = query_staking_report(sdk, &source_address).await?;
+
+    match args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Table => print_table(&report),
+    }
+
+    Ok(())
+}
+
+pub async fn query_staking_report(
+    sdk: &Sdk,
+    source_address: &Address,
+) -> Result<StakingReport, String> {
+    let client = sdk.namada.clone_client();
+    let epoch = rpc::query_epoch(&client).await.map_err(|e| e.to_string())?;
+    let mut validators = rpc::get_delegation_validators(&client, source_address, epoch)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .collect::<Vec<_>>();
+    validators.sort();
+
+    let mut validator_reports = Vec::with_capacity(validators.len());
+    for validator in validators {
+        let bonded = rpc::get_bond_amount_at(&client, source_address, &validator, epoch)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut unbonds = rpc::query_unbond_with_slashing(&client, source_address, &validator)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|((start_epoch, withdraw_epoch), amount)| UnbondReport {
+                amount: amount.to_string(),
+                start_epoch: start_epoch.0,
+                withdrawable_epoch: withdraw_epoch.0,
+                withdrawable: withdraw_epoch <= epoch,
+            })
+            .collect::<Vec<_>>();
+        unbonds.sort_by_key(|unbond| unbond.withdrawable_epoch);
+        let pending_rewards = rpc::query_rewards(
+            &client,
+            &Some(source_address.clone()),
+            &validator,
+            &Some(epoch),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let (state, _epoch) = rpc::get_validator_state(&client, &validator, Some(epoch))
+            .await
+            .map_err(|e| e.to_string())?;
+        let commission = rpc::query_commission_rate(&client, &validator, Some(epoch))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        validator_reports.push(ValidatorReport {
+            validator: validator.to_string(),
+            state: state
+                .map(|state| format!("{:?}", state))
+                .unwrap_or_else(|| "Unknown".to_string()),
+            commission_rate: commission.commission_rate.map(|rate| rate.to_string()),
+            bonded: bonded.to_string(),
+            unbonds,
+            pending_rewards: pending_rewards.to_string(),
+        });
+    }
+
+    Ok(StakingReport {
+        source: source_address.to_string(),
+        epoch: epoch.0,
+        validators: validator_reports,
+    })
+}
+
+fn print_table(report: &StakingReport) {
+    println!(
+        "Staking report for {} at epoch {}",
+        report.source, report.epoch
+    );
+    if report.validators.is_empty() {
+        println!("No bonds");
+        return;
+    }
+
+    println!(
+        "{:<45} {:<12} {:>10} {:>20} {:>20}",
+        "VALIDATOR", "STATE", "COMMISSION", "BONDED (unam)", "REWARDS (unam)"
+    );
+    for validator in &report.validators {
+        println!(
+            "{:<45} {:<12} {:>10} {:>20} {:>20}",
+            validator.validator,
+            validator.state,
+            validator.commission_rate.as_deref().unwrap_or("-"),
+            validator.bonded,
+            validator.pending_rewards
+        );
+        for unbond in &validator.unbonds {
+            println!(
+                "  unbonded {}unam at epoch {}, withdrawable from epoch {} ({})",
+                unbond.amount,
+                unbond.start_epoch,
+                unbond.withdrawable_epoch,
+                if unbond.withdrawable {
+                    "ready"
+                } else {
+                    "pending"
+                }
+            );
+        }
+    }
+}
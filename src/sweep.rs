@@ -0,0 +1,180 @@
+use std::{str::FromStr, time::Duration};
+
+use namada_sdk::{address::Address, rpc, token::Amount, Namada, PaymentAddress};
+use serde_json::json;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{AppConfig, SweepArgs, SweepParams, TxSigningArgs},
+    keystore::{self, PasswordSource},
+    policy,
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    shielding_transfer::execute_shielding_tx,
+    tokens,
+    transparent_transfer::execute_transparent_tx,
+};
+
+// the webhook failing doesn't stop the sweep, the alert is logged either way
+async fn alert(params: &SweepParams, source: &Address, token_name: &str, balance: Amount) {
+    let hot_min = params.hot_min.unwrap_or_default();
+    tracing::warn!(
+        "Hot account {} is low on {}: {} below {}",
+        source,
+        token_name,
+        balance,
+        hot_min
+    );
+    let Some(webhook) = &params.alert_webhook else {
+        return;
+    };
+    let alert = json!({
+        "alert": "hot_balance_low",
+        "source": source.to_string(),
+        "token": token_name,
+        "balance": balance.to_string(),
+        "hot_min": hot_min,
+    });
+    if let Err(e) = reqwest::Client::new()
+        .post(webhook)
+        .json(&alert)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        tracing::warn!("Can't send alert to {}: {}", webhook, e);
+    }
+}
+
+// checks the band once, callers sending from the same account concurrently must hold its lane
+pub async fn sweep(
+    sdk: &Sdk,
+    params: &SweepParams,
+    source: &Address,
+    password: Option<Zeroizing<String>>,
+) -> Result<(), String> {
+    let token = tokens::resolve_token(sdk, &sdk.base_dir, &params.token).await?;
+    let token_name = tokens::token_display(sdk, &sdk.base_dir, &token).await;
+    let balance = rpc::get_token_balance(&sdk.namada.clone_client(), &token, source, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(hot_min) = params.hot_min {
+        if balance < Amount::from_u64(hot_min) {
+            alert(params, source, &token_name, balance).await;
+        }
+    }
+
+    let Some(hot_max) = params.hot_max else {
+        return Ok(());
+    };
+    let excess = match balance.checked_sub(Amount::from_u64(hot_max)) {
+        Some(excess) if !excess.is_zero() => excess,
+        _ => {
+            tracing::info!(
+                "Hot account {} holds {} {}, nothing to sweep",
+                source,
+                balance,
+                token_name
+            );
+            return Ok(());
+        }
+    };
+    let amount = u64::try_from(excess.raw_amount())
+        .map_err(|_| format!("Amount {} to sweep is too large", excess))?;
+    let cold_address = params
+        .cold_address
+        .as_deref()
+        .ok_or_else(|| "--cold-address is required to sweep".to_string())?;
+
+    let (gas_payer, signers) = keystore::find_tx_signers(
+        sdk,
+        &TxSigningArgs {
+            gas_payer: params.gas_payer.clone(),
+            signers: vec![],
+            expiration_timestamp_utc: None,
+            memo: None,
+            request_id: None,
+        },
+        password,
+    )
+    .await?;
+
+    let outgoing = policy::Outgoing {
+        kind: if params.shielded {
+            "Shielding"
+        } else {
+            "Transparent"
+        },
+        source: source.to_string(),
+        target: cold_address.to_string(),
+        token: token.clone(),
+        amount,
+    };
+    let reservation = policy::check_sweep(sdk, &outgoing).await?;
+
+    let result = async {
+        reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+
+        tracing::info!(
+            "Sweeping {} {} from {} to {}...",
+            amount,
+            token_name,
+            source,
+            cold_address
+        );
+        if params.shielded {
+            execute_shielding_tx(
+                sdk,
+                source.clone(),
+                PaymentAddress::from_str(cold_address).map_err(|e| e.to_string())?,
+                token,
+                gas_payer,
+                signers,
+                excess,
+                None,
+                None,
+                None,
+            )
+            .await
+        } else {
+            execute_transparent_tx(
+                sdk,
+                source.clone(),
+                Address::from_str(cold_address).map_err(|e| e.to_string())?,
+                token,
+                gas_payer,
+                signers,
+                excess,
+                None,
+                None,
+                None,
+            )
+            .await
+        }
+    }
+    .await;
+    policy::settle(&sdk.base_dir, &reservation, &result);
+    result?;
+    tracing::info!("Swept {} {} to {}", amount, token_name, cold_address);
+
+    Ok(())
+}
+
+pub async fn sweep_command(sdk: &Sdk, config: &AppConfig, args: &SweepArgs) -> Result<(), String> {
+    if args.sweep.hot_max.is_some() && args.sweep.cold_address.is_none() {
+        return Err("--cold-address is required with --hot-max".to_string());
+    }
+    let password = PasswordSource::from(&config.password).read(false)?;
+    let source = keystore::find_address(sdk, &args.sweep.source).await?;
+
+    let Some(interval) = args.interval else {
+        return sweep(sdk, &args.sweep, &source, Some(password)).await;
+    };
+    loop {
+        if let Err(e) = sweep(sdk, &args.sweep, &source, Some(password.clone())).await {
+            tracing::warn!("Sweep failed: {}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use namada_sdk::{address::Address, rpc};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{keystore, sdk::Sdk};
+
+// IBC denom traces (`transfer/channel-0/uosmo`) are mapped to their Namada token address once
+// and kept in `tokens.json` under the base dir, the mapping never changes for a given trace
+#[derive(Serialize, Deserialize, Default)]
+pub struct TokenCache {
+    #[serde(skip)]
+    path: PathBuf,
+    traces: BTreeMap<String, Address>,
+}
+
+impl TokenCache {
+    pub fn load(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("tokens.json");
+        let mut cache = if path.exists() {
+            let bytes = fs::read(&path)
+                .map_err(|e| format!("Can't read token cache {}: {}", path.display(), e))?;
+            serde_json::from_slice::<TokenCache>(&bytes)
+                .map_err(|e| format!("Invalid token cache {}: {}", path.display(), e))?
+        } else {
+            TokenCache::default()
+        };
+        cache.path = path;
+        Ok(cache)
+    }
+
+    // written to a file of its own then renamed over the cache, so a reader never sees it half
+    // written and concurrent writers don't mix their content
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp_path = self
+            .path
+            .with_extension(format!("json.{:x}.tmp", OsRng.next_u64()));
+        fs::write(&tmp_path, json)
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                format!("Can't write token cache {}: {}", self.path.display(), e)
+            })
+    }
+
+    fn insert(&mut self, trace: String, token: Address) -> Result<(), String> {
+        if self.traces.insert(trace, token.clone()).as_ref() != Some(&token) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn find_trace(&self, token: &Address) -> Option<&String> {
+        self.traces
+            .iter()
+            .find_map(|(trace, address)| (address == token).then_some(trace))
+    }
+}
+
+// accepts a token address, a wallet alias (like `nam`), a full denom trace or a base denom as
+// long as a single trace of it is known to the chain
+pub async fn resolve_token(sdk: &Sdk, base_dir: &Path, token: &str) -> Result<Address, String> {
+    find_token(sdk, base_dir, token).await?.ok_or_else(|| {
+        if token.contains('/') {
+            format!("Denom trace {} is unknown to the chain", token)
+        } else {
+            format!("Unknown token {}", token)
+        }
+    })
+}
+
+// like `resolve_token`, with `None` for a denom unknown to the chain
+pub async fn find_token(
+    sdk: &Sdk,
+    base_dir: &Path,
+    token: &str,
+) -> Result<Option<Address>, String> {
+    if let Ok(address) = keystore::find_address(sdk, token).await {
+        return Ok(Some(address));
+    }
+
+    let mut cache = TokenCache::load(base_dir)?;
+    if let Some(address) = cache.traces.get(token) {
+        return Ok(Some(address.clone()));
+    }
+
+    let base_denom = token.rsplit('/').next().unwrap_or(token).to_string();
+    let ibc_tokens = rpc::query_ibc_tokens(&sdk.namada, Some(base_denom), None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (trace, address) = if token.contains('/') {
+        match ibc_tokens
+            .into_iter()
+            .find(|(trace, _address)| trace == token)
+        {
+            Some(found) => found,
+            None => return Ok(None),
+        }
+    } else {
+        let mut matches = ibc_tokens.into_iter().collect::<Vec<_>>();
+        match matches.len() {
+            0 => return Ok(None),
+            1 => matches.remove(0),
+            _ => {
+                return Err(format!(
+                    "Denom {} is ambiguous, use one of the traces: {}",
+                    token,
+                    matches
+                        .iter()
+                        .map(|(trace, _address)| trace.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }
+    };
+
+    tracing::debug!("Resolved {} to {}", trace, address);
+    cache.insert(trace, address.clone())?;
+    Ok(Some(address))
+}
+
+// the name shown to users: the wallet alias, the denom trace of an IBC token or the address
+pub async fn token_display(sdk: &Sdk, base_dir: &Path, token: &Address) -> String {
+    if let Some(alias) = sdk.namada.wallet.read().await.find_alias(token) {
+        return alias.to_string();
+    }
+
+    let mut cache = match TokenCache::load(base_dir) {
+        Ok(cache) => cache,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return token.to_string();
+        }
+    };
+    if let Some(trace) = cache.find_trace(token) {
+        return trace.clone();
+    }
+
+    // the token itself is returned when it isn't an IBC token
+    let denom = rpc::query_ibc_denom(&sdk.namada, token.to_string(), None).await;
+    if denom != token.to_string() {
+        if let Err(e) = cache.insert(denom.clone(), token.clone()) {
+            tracing::warn!("{}", e);
+        }
+    }
+    denom
+}
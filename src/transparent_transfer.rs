@@ -1,18 +1,17 @@
 use namada_sdk::{
     address::Address,
-    args::{InputAmount, TxBuilder, TxExpiration, TxTransparentTransferData},
-    bytes::HEXLOWER,
+    args::{self, InputAmount, TxBuilder, TxExpiration, TxTransparentTransferData},
     key::common,
-    signing::default_sign,
+    signing::SigningTxData,
     time::DateTimeUtc,
     token::{self, DenominatedAmount},
-    tx::data::GasLimit,
+    tx::{data::GasLimit, Tx},
     Namada, DEFAULT_GAS_LIMIT,
 };
 
-use crate::{sdk::Sdk, utils};
+use crate::{pipeline, sdk::Sdk};
 
-pub async fn execute_transparent_tx(
+pub async fn build_transparent_tx(
     sdk: &Sdk,
     source_address: Address,
     target_address: Address,
@@ -22,7 +21,7 @@ pub async fn execute_transparent_tx(
     amount: token::Amount,
     memo: Option<String>,
     expiration: Option<i64>,
-) -> Result<bool, String> {
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
     let tx_transfer_data = TxTransparentTransferData {
         source: source_address.clone(),
         target: target_address.clone(),
@@ -30,7 +29,27 @@ pub async fn execute_transparent_tx(
         amount: InputAmount::Unvalidated(DenominatedAmount::native(amount)),
     };
 
-    let mut transfer_tx_builder = sdk.namada.new_transparent_transfer(vec![tx_transfer_data]);
+    build_transparent_batch_tx(
+        sdk,
+        vec![tx_transfer_data],
+        gas_payer,
+        signers,
+        memo,
+        expiration,
+    )
+    .await
+}
+
+// several transfers in a single tx, they are all applied or none is
+pub async fn build_transparent_batch_tx(
+    sdk: &Sdk,
+    transfers: Vec<TxTransparentTransferData>,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut transfer_tx_builder = sdk.namada.new_transparent_transfer(transfers);
     transfer_tx_builder = transfer_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
     transfer_tx_builder = transfer_tx_builder.wrapper_fee_payer(gas_payer);
     if let Some(memo) = memo {
@@ -38,48 +57,74 @@ pub async fn execute_transparent_tx(
     }
     if let Some(expiration) = expiration {
         transfer_tx_builder = transfer_tx_builder.expiration(TxExpiration::Custom(
-            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+            DateTimeUtc::from_unix_timestamp(expiration)
+                .ok_or_else(|| format!("Invalid expiration timestamp {}", expiration))?,
         ));
     }
     transfer_tx_builder = transfer_tx_builder.signing_keys(signers);
 
-    let (mut transfer_tx, signing_data) = transfer_tx_builder
+    let (transfer_tx, signing_data) = transfer_tx_builder
         .build(&sdk.namada)
         .await
         .map_err(|e| e.to_string())?;
 
-    sdk.namada
-        .sign(
-            &mut transfer_tx,
-            &transfer_tx_builder.tx,
-            signing_data,
-            default_sign,
-            (),
-        )
-        .await
-        .expect("unable to sign tx");
+    Ok((transfer_tx, signing_data, transfer_tx_builder.tx))
+}
+
+pub async fn execute_transparent_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    target_address: Address,
+    token_address: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut transfer_tx, signing_data, tx_args) = build_transparent_tx(
+        sdk,
+        source_address,
+        target_address,
+        token_address,
+        gas_payer,
+        signers,
+        amount,
+        memo,
+        expiration,
+    )
+    .await?;
 
-    let tx = sdk
-        .namada
-        .submit(transfer_tx.clone(), &transfer_tx_builder.tx)
-        .await;
+    pipeline::sign_tx(sdk, &mut transfer_tx, &tx_args, signing_data, "Transparent").await?;
 
-    tracing::info!(
-        "Transparent wrapper tx hash: {:?}",
-        transfer_tx.wrapper_hash().map(|h| HEXLOWER.encode(&h.0))
-    );
+    pipeline::submit_tx(sdk, transfer_tx, &tx_args, "Transparent", request_id)
+        .await
+        .map(|_| true)
+}
 
-    tracing::debug!("tx result: {:?}", tx);
+pub async fn execute_transparent_batch_tx(
+    sdk: &Sdk,
+    transfers: Vec<TxTransparentTransferData>,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut transfer_tx, signing_data, tx_args) =
+        build_transparent_batch_tx(sdk, transfers, gas_payer, signers, memo, expiration).await?;
 
-    if utils::is_tx_rejected(&transfer_tx, &tx) {
-        match tx {
-            Ok(tx) => {
-                let errors = utils::get_tx_errors(&transfer_tx, &tx).unwrap_or_default();
-                Err(errors)
-            }
-            Err(e) => Err(e.to_string()),
-        }
-    } else {
-        Ok(true)
-    }
+    pipeline::sign_tx(
+        sdk,
+        &mut transfer_tx,
+        &tx_args,
+        signing_data,
+        "Transparent batch",
+    )
+    .await?;
+
+    pipeline::submit_tx(sdk, transfer_tx, &tx_args, "Transparent batch", request_id)
+        .await
+        .map(|_| true)
 }
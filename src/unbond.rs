@@ -0,0 +1,76 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, TxBuilder, TxExpiration},
+    key::common,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    token,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{pipeline, sdk::Sdk};
+
+pub async fn build_unbond_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut unbond_tx_builder = sdk
+        .namada
+        .new_unbond(validator, amount)
+        .source(source_address);
+    unbond_tx_builder = unbond_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    unbond_tx_builder = unbond_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        unbond_tx_builder = unbond_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        unbond_tx_builder = unbond_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    unbond_tx_builder = unbond_tx_builder.signing_keys(signers);
+
+    let (unbond_tx, signing_data, _latest_withdrawal) = unbond_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((unbond_tx, signing_data, unbond_tx_builder.tx))
+}
+
+pub async fn execute_unbond_tx(
+    sdk: &Sdk,
+    source_address: Address,
+    validator: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut unbond_tx, signing_data, tx_args) = build_unbond_tx(
+        sdk,
+        source_address,
+        validator,
+        gas_payer,
+        signers,
+        amount,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(sdk, &mut unbond_tx, &tx_args, signing_data, "Unbond").await?;
+
+    pipeline::submit_tx(sdk, unbond_tx, &tx_args, "Unbond", request_id)
+        .await
+        .map(|_| true)
+}
@@ -1,22 +1,21 @@
 use namada_sdk::{
     address::Address,
-    args::{InputAmount, TxBuilder, TxExpiration, TxUnshieldingTransferData},
-    bytes::HEXLOWER,
+    args::{self, InputAmount, TxBuilder, TxExpiration, TxUnshieldingTransferData},
     key::common,
     masp_primitives::{
         transaction::components::sapling::builder::RngBuildParams, zip32::PseudoExtendedKey,
     },
-    signing::default_sign,
+    signing::SigningTxData,
     time::DateTimeUtc,
     token::{self, DenominatedAmount},
-    tx::data::GasLimit,
+    tx::{data::GasLimit, Tx},
     Namada, DEFAULT_GAS_LIMIT,
 };
 use rand_core::OsRng;
 
-use crate::{sdk::Sdk, utils};
+use crate::{pipeline, sdk::Sdk};
 
-pub async fn execute_unshielding_tx(
+pub async fn build_unshielding_tx(
     sdk: &Sdk,
     target_address: Address,
     spending_key: PseudoExtendedKey,
@@ -26,7 +25,7 @@ pub async fn execute_unshielding_tx(
     amount: token::Amount,
     memo: Option<String>,
     expiration: Option<i64>,
-) -> Result<bool, String> {
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
     let tx_transfer_data = TxUnshieldingTransferData {
         target: target_address,
         token: token_address.clone(),
@@ -45,48 +44,48 @@ pub async fn execute_unshielding_tx(
     }
     if let Some(expiration) = expiration {
         transfer_tx_builder = transfer_tx_builder.expiration(TxExpiration::Custom(
-            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+            DateTimeUtc::from_unix_timestamp(expiration)
+                .ok_or_else(|| format!("Invalid expiration timestamp {}", expiration))?,
         ));
     }
     transfer_tx_builder = transfer_tx_builder.signing_keys(signers);
 
-    let (mut transfer_tx, signing_data) = transfer_tx_builder
+    let (transfer_tx, signing_data) = transfer_tx_builder
         .build(&sdk.namada, &mut bparams)
         .await
         .map_err(|e| e.to_string())?;
 
-    sdk.namada
-        .sign(
-            &mut transfer_tx,
-            &transfer_tx_builder.tx,
-            signing_data,
-            default_sign,
-            (),
-        )
-        .await
-        .expect("unable to sign tx");
-
-    let tx = sdk
-        .namada
-        .submit(transfer_tx.clone(), &transfer_tx_builder.tx)
-        .await;
+    Ok((transfer_tx, signing_data, transfer_tx_builder.tx))
+}
 
-    tracing::info!(
-        "Unshielding wrapper tx hash: {:?}",
-        transfer_tx.wrapper_hash().map(|h| HEXLOWER.encode(&h.0))
-    );
+pub async fn execute_unshielding_tx(
+    sdk: &Sdk,
+    target_address: Address,
+    spending_key: PseudoExtendedKey,
+    token_address: Address,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    amount: token::Amount,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut transfer_tx, signing_data, tx_args) = build_unshielding_tx(
+        sdk,
+        target_address,
+        spending_key,
+        token_address,
+        gas_payer,
+        signers,
+        amount,
+        memo,
+        expiration,
+    )
+    .await?;
 
-    tracing::debug!("tx result: {:?}", tx);
+    pipeline::sign_tx(sdk, &mut transfer_tx, &tx_args, signing_data, "Unshielding").await?;
 
-    if utils::is_tx_rejected(&transfer_tx, &tx) {
-        match tx {
-            Ok(tx) => {
-                let errors = utils::get_tx_errors(&transfer_tx, &tx).unwrap_or_default();
-                Err(errors)
-            }
-            Err(e) => Err(e.to_string()),
-        }
-    } else {
-        Ok(true)
-    }
+    pipeline::submit_tx(sdk, transfer_tx, &tx_args, "Unshielding", request_id)
+        .await
+        .map(|_| true)
 }
@@ -1,4 +1,7 @@
-use namada_sdk::tx::{either, ProcessTxResponse, Tx};
+use namada_sdk::{
+    address::Address,
+    tx::{either, ProcessTxResponse, Tx},
+};
 
 pub(crate) fn is_tx_rejected(
     tx: &Tx,
@@ -37,3 +40,12 @@ pub(crate) fn get_tx_errors(tx: &Tx, tx_response: &ProcessTxResponse) -> Option<
         _ => None,
     }
 }
+
+pub(crate) fn get_initialized_accounts(tx: &Tx, tx_response: &ProcessTxResponse) -> Vec<Address> {
+    let cmt = tx.first_commitments().unwrap().to_owned();
+    let wrapper_hash = tx.wrapper_hash();
+    tx_response
+        .is_applied_and_valid(wrapper_hash.as_ref(), &cmt)
+        .map(|result| result.initialized_accounts.clone())
+        .unwrap_or_default()
+}
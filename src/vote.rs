@@ -0,0 +1,74 @@
+use namada_sdk::{
+    address::Address,
+    args::{self, TxBuilder, TxExpiration},
+    key::common,
+    signing::SigningTxData,
+    time::DateTimeUtc,
+    tx::{data::GasLimit, Tx},
+    Namada, DEFAULT_GAS_LIMIT,
+};
+
+use crate::{pipeline, sdk::Sdk};
+
+pub async fn build_vote_tx(
+    sdk: &Sdk,
+    voter_address: Address,
+    proposal_id: u64,
+    vote: String,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+) -> Result<(Tx, SigningTxData, args::Tx), String> {
+    let mut vote_tx_builder = sdk
+        .namada
+        .new_proposal_vote(proposal_id, vote, voter_address);
+    vote_tx_builder = vote_tx_builder.gas_limit(GasLimit::from(DEFAULT_GAS_LIMIT));
+    vote_tx_builder = vote_tx_builder.wrapper_fee_payer(gas_payer);
+    if let Some(memo) = memo {
+        vote_tx_builder = vote_tx_builder.memo(memo.as_bytes().to_vec())
+    }
+    if let Some(expiration) = expiration {
+        vote_tx_builder = vote_tx_builder.expiration(TxExpiration::Custom(
+            DateTimeUtc::from_unix_timestamp(expiration).unwrap(),
+        ));
+    }
+    vote_tx_builder = vote_tx_builder.signing_keys(signers);
+
+    let (vote_tx, signing_data) = vote_tx_builder
+        .build(&sdk.namada)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((vote_tx, signing_data, vote_tx_builder.tx))
+}
+
+pub async fn execute_vote_tx(
+    sdk: &Sdk,
+    voter_address: Address,
+    proposal_id: u64,
+    vote: String,
+    gas_payer: common::PublicKey,
+    signers: Vec<common::PublicKey>,
+    memo: Option<String>,
+    expiration: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<bool, String> {
+    let (mut vote_tx, signing_data, tx_args) = build_vote_tx(
+        sdk,
+        voter_address,
+        proposal_id,
+        vote,
+        gas_payer,
+        signers,
+        memo,
+        expiration,
+    )
+    .await?;
+
+    pipeline::sign_tx(sdk, &mut vote_tx, &tx_args, signing_data, "Vote").await?;
+
+    pipeline::submit_tx(sdk, vote_tx, &tx_args, "Vote", request_id)
+        .await
+        .map(|_| true)
+}
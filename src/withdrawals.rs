@@ -0,0 +1,707 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use namada_sdk::{
+    address::Address,
+    args::{InputAmount, TxTransparentTransferData},
+    masp_primitives::zip32::{ExtendedSpendingKey as ExtendedSpendingKeyMasp, PseudoExtendedKey},
+    rpc,
+    token::{Amount, DenominatedAmount},
+    Namada,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{
+    config::TxSigningArgs,
+    keystore,
+    lanes::{self, Lanes},
+    pipeline, policy,
+    requests::{RequestStatus, RequestStore},
+    reveal_pk::reveal_pk_if_needed,
+    sdk::Sdk,
+    shielded::{self, SpendingKeys},
+    tokens,
+    transparent_transfer::execute_transparent_batch_tx,
+    unshielding_transfer::execute_unshielding_tx,
+};
+
+// a withdrawal is queued, then processing once its tx is being built, then done or failed
+pub const QUEUED: &str = "queued";
+pub const PROCESSING: &str = "processing";
+pub const DONE: &str = "done";
+pub const FAILED: &str = "failed";
+
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawalKind {
+    Transparent,
+    Unshielding,
+}
+
+impl WithdrawalKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WithdrawalKind::Transparent => "transparent",
+            WithdrawalKind::Unshielding => "unshielding",
+        }
+    }
+
+    // the tx kind, as checked by the policy
+    pub fn label(&self) -> &'static str {
+        match self {
+            WithdrawalKind::Transparent => "Transparent",
+            WithdrawalKind::Unshielding => "Unshielding",
+        }
+    }
+}
+
+impl FromStr for WithdrawalKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "transparent" => Ok(WithdrawalKind::Transparent),
+            "unshielding" => Ok(WithdrawalKind::Unshielding),
+            _ => Err(format!("Unknown withdrawal kind {}", kind)),
+        }
+    }
+}
+
+fn lane(kind: WithdrawalKind, source: &str) -> String {
+    match kind {
+        // already the address, checked when queued
+        WithdrawalKind::Transparent => lanes::transparent_lane(source),
+        WithdrawalKind::Unshielding => lanes::shielded_lane(source),
+    }
+}
+
+// what the queue shares with the daemon: the withdrawals lock the same lanes as its handlers
+pub struct QueueContext {
+    pub sdk: Arc<Sdk>,
+    pub password: Zeroizing<String>,
+    pub masp_indexer_url: String,
+    pub spending_keys: SpendingKeys,
+    pub lanes: Arc<Lanes>,
+}
+
+// the source is the address of a transparent withdrawal and the spending key alias of an
+// unshielding one
+pub struct NewWithdrawal {
+    pub request_id: String,
+    pub kind: WithdrawalKind,
+    pub source: String,
+    pub target: String,
+    pub token: String,
+    pub amount: u64,
+    pub gas_payer: String,
+    pub signers: Vec<String>,
+    pub memo: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Withdrawal {
+    pub id: i64,
+    pub request_id: String,
+    pub kind: WithdrawalKind,
+    pub source: String,
+    pub target: String,
+    pub token: String,
+    pub amount: u64,
+    pub gas_payer: String,
+    pub signers: Vec<String>,
+    pub memo: Option<String>,
+    pub status: String,
+    pub batch_id: Option<String>,
+    pub tx_hash: Option<String>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Withdrawal {
+    // transparent withdrawals sharing the source and the signing options go in the same tx
+    fn batches_with(&self, other: &Withdrawal) -> bool {
+        self.kind == WithdrawalKind::Transparent
+            && other.kind == WithdrawalKind::Transparent
+            && self.source == other.source
+            && self.gas_payer == other.gas_payer
+            && self.signers == other.signers
+            && self.memo == other.memo
+    }
+
+    fn outgoing(&self) -> Result<policy::Outgoing, String> {
+        Ok(policy::Outgoing {
+            kind: self.kind.label(),
+            source: self.source.clone(),
+            target: self.target.clone(),
+            token: Address::from_str(&self.token).map_err(|e| e.to_string())?,
+            amount: self.amount,
+        })
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct LaneSummary {
+    pub queued: u64,
+    pub processing: u64,
+    pub done: u64,
+    pub failed: u64,
+}
+
+const COLUMNS: &str = "id, request_id, kind, source, target, token, amount, gas_payer, signers, \
+    memo, status, batch_id, tx_hash, error, created_at, updated_at";
+
+fn read_withdrawal(row: &Row<'_>) -> rusqlite::Result<Withdrawal> {
+    let kind: String = row.get(2)?;
+    let signers: String = row.get(8)?;
+    Ok(Withdrawal {
+        id: row.get(0)?,
+        request_id: row.get(1)?,
+        kind: WithdrawalKind::from_str(&kind).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into())
+        })?,
+        source: row.get(3)?,
+        target: row.get(4)?,
+        token: row.get(5)?,
+        amount: row.get::<_, i64>(6)? as u64,
+        gas_payer: row.get(7)?,
+        signers: signers
+            .split(',')
+            .filter(|signer| !signer.is_empty())
+            .map(str::to_string)
+            .collect(),
+        memo: row.get(9)?,
+        status: row.get(10)?,
+        batch_id: row.get(11)?,
+        tx_hash: row.get(12)?,
+        error: row.get(13)?,
+        created_at: row.get(14)?,
+        updated_at: row.get(15)?,
+    })
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// `withdrawals.sqlite` under the base dir, withdrawals are processed in id order within a lane
+pub struct WithdrawalQueue {
+    conn: Connection,
+}
+
+impl WithdrawalQueue {
+    pub fn open(base_dir: &Path) -> Result<Self, String> {
+        let path = base_dir.join("withdrawals.sqlite");
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Can't open withdrawal queue {}: {}", path.display(), e))?;
+        conn.busy_timeout(Duration::from_secs(10))
+            .and_then(|_| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS withdrawals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                request_id TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                source TEXT NOT NULL,
+                target TEXT NOT NULL,
+                token TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                gas_payer TEXT NOT NULL,
+                signers TEXT NOT NULL,
+                memo TEXT,
+                status TEXT NOT NULL,
+                batch_id TEXT,
+                tx_hash TEXT,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS withdrawals_lane ON withdrawals(kind, source, status);",
+                )
+            })
+            .map_err(|e| {
+                format!(
+                    "Can't initialize withdrawal queue {}: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+        Ok(Self { conn })
+    }
+
+    // queuing the same request id twice returns the first withdrawal
+    pub fn push(&self, withdrawal: &NewWithdrawal) -> Result<Withdrawal, String> {
+        let at = now();
+        self.conn
+            .execute(
+                "INSERT INTO withdrawals (request_id, kind, source, target, token, amount,
+                    gas_payer, signers, memo, status, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)
+                ON CONFLICT(request_id) DO NOTHING",
+                params![
+                    withdrawal.request_id,
+                    withdrawal.kind.as_str(),
+                    withdrawal.source,
+                    withdrawal.target,
+                    withdrawal.token,
+                    withdrawal.amount as i64,
+                    withdrawal.gas_payer,
+                    withdrawal.signers.join(","),
+                    withdrawal.memo,
+                    QUEUED,
+                    at
+                ],
+            )
+            .map_err(|e| format!("Can't queue withdrawal: {}", e))?;
+        self.query_one("request_id = ?1", params![withdrawal.request_id])?
+            .ok_or_else(|| format!("Withdrawal {} wasn't queued", withdrawal.request_id))
+    }
+
+    fn query_one(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Option<Withdrawal>, String> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM withdrawals WHERE {}", COLUMNS, filter),
+                params,
+                read_withdrawal,
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn query(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<Withdrawal>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM withdrawals WHERE {}",
+                COLUMNS, filter
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params, read_withdrawal)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    pub fn find(&self, request_id: &str) -> Result<Option<Withdrawal>, String> {
+        self.query_one("request_id = ?1", params![request_id])
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Withdrawal>, String> {
+        self.query_one("id = ?1", params![id])
+    }
+
+    // the most recent first
+    pub fn list(&self, status: Option<&str>, limit: usize) -> Result<Vec<Withdrawal>, String> {
+        self.query(
+            "?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+            params![status, limit as i64],
+        )
+    }
+
+    // counts by lane and status
+    pub fn summary(&self) -> Result<BTreeMap<String, LaneSummary>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT kind, source, status, COUNT(*) FROM withdrawals GROUP BY kind, source, status")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)? as u64,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut summary = BTreeMap::<String, LaneSummary>::new();
+        for row in rows {
+            let (kind, source, status, count) = row.map_err(|e| e.to_string())?;
+            let lane_summary = summary
+                .entry(lane(WithdrawalKind::from_str(&kind)?, &source))
+                .or_default();
+            match status.as_str() {
+                QUEUED => lane_summary.queued = count,
+                PROCESSING => lane_summary.processing = count,
+                DONE => lane_summary.done = count,
+                _ => lane_summary.failed = count,
+            }
+        }
+        Ok(summary)
+    }
+
+    // the lanes with work left
+    fn pending_lanes(&self) -> Result<Vec<(WithdrawalKind, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT kind, source FROM withdrawals WHERE status IN (?1, ?2)")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![QUEUED, PROCESSING], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.map(|row| {
+            let (kind, source) = row.map_err(|e| e.to_string())?;
+            Ok((WithdrawalKind::from_str(&kind)?, source))
+        })
+        .collect()
+    }
+
+    // a batch left processing by a previous run comes first, otherwise the oldest queued
+    // withdrawal with the ones that can share its tx
+    fn next_batch(
+        &self,
+        kind: WithdrawalKind,
+        source: &str,
+        max_size: usize,
+    ) -> Result<Vec<Withdrawal>, String> {
+        if let Some(processing) = self.query_one(
+            "kind = ?1 AND source = ?2 AND status = ?3 ORDER BY id LIMIT 1",
+            params![kind.as_str(), source, PROCESSING],
+        )? {
+            return self.query(
+                "batch_id = ?1 ORDER BY id",
+                params![processing.batch_id.unwrap_or_default()],
+            );
+        }
+
+        let queued = self.query(
+            "kind = ?1 AND source = ?2 AND status = ?3 ORDER BY id LIMIT ?4",
+            params![kind.as_str(), source, QUEUED, max_size as i64],
+        )?;
+        let Some(first) = queued.first().cloned() else {
+            return Ok(vec![]);
+        };
+        Ok(queued
+            .into_iter()
+            .enumerate()
+            .take_while(|(idx, withdrawal)| *idx == 0 || first.batches_with(withdrawal))
+            .map(|(_, withdrawal)| withdrawal)
+            .collect())
+    }
+
+    // all the withdrawals of the batch are started together, or none is
+    fn start_batch(&mut self, batch: &[Withdrawal], batch_id: &str) -> Result<(), String> {
+        let at = now();
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        for withdrawal in batch {
+            tx.execute(
+                "UPDATE withdrawals SET status = ?1, batch_id = ?2, updated_at = ?3 WHERE id = ?4",
+                params![PROCESSING, batch_id, at, withdrawal.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // a withdrawal refused before its batch is started
+    fn fail(&self, id: i64, error: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE withdrawals SET status = ?1, error = ?2, updated_at = ?3 WHERE id = ?4",
+                params![FAILED, error, now(), id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn finish_batch(
+        &self,
+        batch_id: &str,
+        status: &str,
+        tx_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE withdrawals SET status = ?1, tx_hash = ?2, error = ?3, updated_at = ?4
+                WHERE batch_id = ?5",
+                params![status, tx_hash, error, now(), batch_id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// one task per lane with work left, the lanes are independent from each other
+pub async fn process_queue(ctx: Arc<QueueContext>, batch_size: usize) {
+    let busy_lanes = Arc::new(Mutex::new(HashSet::new()));
+    loop {
+        match WithdrawalQueue::open(&ctx.sdk.base_dir).and_then(|queue| queue.pending_lanes()) {
+            Ok(lanes) => {
+                for (kind, source) in lanes {
+                    let lane = lane(kind, &source);
+                    if !busy_lanes.lock().unwrap().insert(lane.clone()) {
+                        continue;
+                    }
+                    let ctx = ctx.clone();
+                    let busy_lanes = busy_lanes.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = process_lane(&ctx, kind, &source, batch_size).await {
+                            tracing::warn!("Withdrawals of {}: {}", lane, e);
+                        }
+                        busy_lanes.lock().unwrap().remove(&lane);
+                    });
+                }
+            }
+            Err(e) => tracing::warn!("{}", e),
+        }
+        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+    }
+}
+
+async fn process_lane(
+    ctx: &QueueContext,
+    kind: WithdrawalKind,
+    source: &str,
+    batch_size: usize,
+) -> Result<(), String> {
+    let _lane = ctx.lanes.lock(&lane(kind, source)).await;
+    loop {
+        let batch =
+            WithdrawalQueue::open(&ctx.sdk.base_dir)?.next_batch(kind, source, batch_size)?;
+        if batch.is_empty() || !process_batch(ctx, &batch).await? {
+            return Ok(());
+        }
+    }
+}
+
+// returns false when the batch has to wait, for funds with its withdrawals left queued or for a
+// submitted tx whose outcome isn't known yet
+async fn process_batch(ctx: &QueueContext, batch: &[Withdrawal]) -> Result<bool, String> {
+    let sdk = &ctx.sdk;
+    let first = &batch[0];
+    let batch_id = first
+        .batch_id
+        .clone()
+        .unwrap_or_else(|| format!("withdrawals/{}-{}", first.id, batch[batch.len() - 1].id));
+
+    // a batch submitted before is never built again: its outcome is already known or its recorded
+    // tx is resumed
+    if let Some(record) = RequestStore::open(&sdk.base_dir)?.get(&batch_id)? {
+        let error = match record.status {
+            RequestStatus::Submitted => pipeline::check_prior(sdk, Some(&batch_id)).await.err(),
+            _ => None,
+        };
+        return finish_batch(ctx, batch, &batch_id, error);
+    }
+
+    let signing_args = TxSigningArgs {
+        gas_payer: first.gas_payer.clone(),
+        signers: first.signers.clone(),
+        expiration_timestamp_utc: None,
+        memo: first.memo.clone(),
+        request_id: Some(batch_id.clone()),
+    };
+    let (gas_payer, signers) =
+        keystore::find_tx_signers(sdk, &signing_args, Some(ctx.password.clone())).await?;
+
+    let mut totals = BTreeMap::<Address, u64>::new();
+    let mut transfers = Vec::with_capacity(batch.len());
+    for withdrawal in batch {
+        let token = tokens::resolve_token(sdk, &sdk.base_dir, &withdrawal.token).await?;
+        *totals.entry(token.clone()).or_default() += withdrawal.amount;
+        transfers.push((withdrawal, token));
+    }
+
+    let result = match first.kind {
+        WithdrawalKind::Transparent => {
+            let source = Address::from_str(&first.source).map_err(|e| e.to_string())?;
+            if first.batch_id.is_none() {
+                for (token, total) in &totals {
+                    let balance =
+                        rpc::get_token_balance(&sdk.namada.clone_client(), token, &source, None)
+                            .await
+                            .unwrap_or_default();
+                    if !balance.can_spend(&Amount::from_u64(*total)) {
+                        tracing::warn!(
+                            "Withdrawals of {} wait for funds: {} {} needed, got {}",
+                            source,
+                            total,
+                            token,
+                            balance
+                        );
+                        return Ok(false);
+                    }
+                }
+            }
+            let transfers = transfers
+                .into_iter()
+                .map(|(withdrawal, token)| {
+                    Ok(TxTransparentTransferData {
+                        source: source.clone(),
+                        target: Address::from_str(&withdrawal.target).map_err(|e| e.to_string())?,
+                        token,
+                        amount: InputAmount::Unvalidated(DenominatedAmount::native(
+                            Amount::from_u64(withdrawal.amount),
+                        )),
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            if first.batch_id.is_none() {
+                if !reserve(sdk, batch, &batch_id).await? {
+                    return Ok(true);
+                }
+            }
+            WithdrawalQueue::open(&sdk.base_dir)?.start_batch(batch, &batch_id)?;
+            reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+            tracing::info!(
+                "Processing {} withdrawal(s) of {} as {}",
+                batch.len(),
+                source,
+                batch_id
+            );
+            execute_transparent_batch_tx(
+                sdk,
+                transfers,
+                gas_payer,
+                signers,
+                first.memo.clone(),
+                None,
+                Some(&batch_id),
+            )
+            .await
+        }
+        WithdrawalKind::Unshielding => {
+            let (_, spending_key) = ctx.spending_keys.get(Some(&first.source))?;
+            let (withdrawal, token) = transfers.remove(0);
+            // spent notes are only known after a sync, the lane lock keeps them from being picked
+            // again in the meantime
+            shielded::sync(
+                sdk,
+                &ctx.masp_indexer_url,
+                &ctx.spending_keys.viewing_keys(),
+                shielded::NoShutdown,
+            )
+            .await?;
+            if first.batch_id.is_none() {
+                let balance =
+                    shielded::balance(sdk, &shielded::viewing_key(spending_key), &token).await?;
+                if !balance.can_spend(&Amount::from_u64(withdrawal.amount)) {
+                    tracing::warn!(
+                        "Withdrawals of {} wait for funds: {} {} needed, got {}",
+                        first.source,
+                        withdrawal.amount,
+                        token,
+                        balance
+                    );
+                    return Ok(false);
+                }
+                if !reserve(sdk, batch, &batch_id).await? {
+                    return Ok(true);
+                }
+            }
+
+            WithdrawalQueue::open(&sdk.base_dir)?.start_batch(batch, &batch_id)?;
+            reveal_pk_if_needed(sdk, gas_payer.clone()).await?;
+            tracing::info!(
+                "Processing unshielding withdrawal {} of {}",
+                withdrawal.id,
+                first.source
+            );
+            execute_unshielding_tx(
+                sdk,
+                Address::from_str(&withdrawal.target).map_err(|e| e.to_string())?,
+                PseudoExtendedKey::from(ExtendedSpendingKeyMasp::from(spending_key)),
+                token,
+                gas_payer,
+                signers,
+                Amount::from_u64(withdrawal.amount),
+                withdrawal.memo.clone(),
+                None,
+                Some(&batch_id),
+            )
+            .await
+        }
+    };
+
+    finish_batch(ctx, batch, &batch_id, result.err())
+}
+
+// the policy may have changed since the withdrawals were queued, so each one is checked again and
+// its amount reserved under the batch id. A refused withdrawal fails on its own and the lane moves
+// on to the next batch, which is built without it. A batch left processing by a previous run keeps
+// the reservations made then
+async fn reserve(sdk: &Sdk, batch: &[Withdrawal], batch_id: &str) -> Result<bool, String> {
+    let mut reservations = Vec::with_capacity(batch.len());
+    for withdrawal in batch {
+        match policy::check(sdk, &withdrawal.outgoing()?, Some(batch_id)).await {
+            Ok(reservation) => reservations.push(reservation),
+            Err(e) => {
+                for reservation in &reservations {
+                    policy::release(&sdk.base_dir, reservation);
+                }
+                tracing::warn!("Withdrawal {} refused: {}", withdrawal.id, e);
+                WithdrawalQueue::open(&sdk.base_dir)?.fail(withdrawal.id, &e)?;
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+// the outcome comes from the request store once the tx was submitted, otherwise the batch failed
+// before submission with `error`. The reservations of a submitted batch are settled by `pipeline`
+// with its final status, those of a batch never submitted are released here
+fn finish_batch(
+    ctx: &QueueContext,
+    batch: &[Withdrawal],
+    batch_id: &str,
+    error: Option<String>,
+) -> Result<bool, String> {
+    let base_dir = &ctx.sdk.base_dir;
+    let queue = WithdrawalQueue::open(base_dir)?;
+    let record = RequestStore::open(base_dir)?.get(batch_id)?;
+    // the tx may still be included, e.g. after a broadcast error: the withdrawals stay processing
+    // and the next pass resumes the recorded tx instead of ever paying them twice
+    if let Some(record) = record
+        .as_ref()
+        .filter(|record| record.status == RequestStatus::Submitted)
+    {
+        tracing::warn!(
+            "Withdrawals of {} wait for tx {}: {}",
+            batch_id,
+            record.tx_hash,
+            error.as_deref().unwrap_or("no final status yet")
+        );
+        return Ok(false);
+    }
+    if record.is_none() {
+        policy::settle_request(base_dir, batch_id, false);
+    }
+    match record {
+        Some(record) if record.status == RequestStatus::Applied => {
+            queue.finish_batch(batch_id, DONE, Some(&record.tx_hash), None)
+        }
+        Some(record) => queue.finish_batch(
+            batch_id,
+            FAILED,
+            Some(&record.tx_hash),
+            record.error.as_deref().or(error.as_deref()),
+        ),
+        None => queue.finish_batch(batch_id, FAILED, None, error.as_deref()),
+    }
+    .map(|_| true)
+}